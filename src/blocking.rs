@@ -0,0 +1,171 @@
+//! # Blocking HIBP Client
+//!
+//! A synchronous wrapper around [`HibpClient`] for callers that don't run a Tokio runtime
+//! themselves, mirroring the `reqwest::blocking` module. Enabled with the `blocking` feature.
+//!
+//! Each call drives the async client to completion on an internal current-thread runtime, so
+//! these methods must not be called from within an async context.
+//!
+//! ## Example Usage
+//! ```
+//! use hibp_client::blocking::HibpClientBlocking;
+//!
+//! fn main() {
+//!     let hibp = HibpClientBlocking::new("my-app/1.0".to_string(), 10).unwrap();
+//!
+//!     if let Ok(count) = hibp.check_password("hunter2") {
+//!         println!("Password appeared in {count} breaches.");
+//!     }
+//! }
+//! ```
+
+#![allow(dead_code)]
+
+use std::future::Future;
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::hibp::{
+    Breach, DomainMatch, HibpClient, HibpError, HibpResult, Paste, SubscribedDomain,
+    SubscriptionStatus,
+};
+
+/// Blocking client for accessing the HIBP API.
+/// Create an instance using [`HibpClientBlocking::new`], which takes the same arguments as
+/// [`HibpClient::new`].
+pub struct HibpClientBlocking {
+    inner: HibpClient,
+    runtime: Runtime,
+}
+
+impl HibpClientBlocking {
+    // ╔══════════════════════════════════════════════════════════════════════════════════════════════╗
+    // ║                                                                                              ║
+    // ║                                       Management                                             ║
+    // ║                                                                                              ║
+    // ╚══════════════════════════════════════════════════════════════════════════════════════════════╝
+
+    /// Creates a new blocking HIBP client.
+    ///
+    /// # Errors
+    /// - Returns [`HibpError::ClientBuildError`] if the async client fails to build
+    /// - Returns [`HibpError::ClientBuildError`] if the internal runtime fails to build
+    pub fn new(user_agent: String, time_out: u64) -> HibpResult<Self> {
+        let inner = HibpClient::new(user_agent, time_out)?;
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| HibpError::ClientBuildError(e.to_string()))?;
+
+        Ok(Self { inner, runtime })
+    }
+
+    /// Returns the wrapped async client.
+    pub fn client(&self) -> &HibpClient {
+        &self.inner
+    }
+
+    /// Returns the wrapped async client mutably (e.g. to change the API key or timeout).
+    pub fn client_mut(&mut self) -> &mut HibpClient {
+        &mut self.inner
+    }
+
+    /// Runs any async client method to completion, for methods without a blocking counterpart.
+    ///
+    /// ```
+    /// let breaches = hibp.block_on(|client| client.get_all_breaches())?;
+    /// ```
+    pub fn block_on<'a, F, T>(&'a self, f: impl FnOnce(&'a HibpClient) -> F) -> T
+    where
+        F: Future<Output = T> + 'a,
+    {
+        self.runtime.block_on(f(&self.inner))
+    }
+
+    /// See [`HibpClient::get_subscription`].
+    pub fn get_subscription(&self) -> HibpResult<SubscriptionStatus> {
+        self.block_on(|c| c.get_subscription())
+    }
+
+    /// See [`HibpClient::get_subscribed_domains`].
+    pub fn get_subscribed_domains(&self) -> HibpResult<Vec<SubscribedDomain>> {
+        self.block_on(|c| c.get_subscribed_domains())
+    }
+
+    /// See [`HibpClient::change_api_key`].
+    pub fn change_api_key(&mut self, new_api_key: String) {
+        self.inner.change_api_key(new_api_key);
+    }
+
+    /// See [`HibpClient::change_time_out`].
+    pub fn change_time_out(&mut self, new_time_out: u64) -> HibpResult<()> {
+        self.inner.change_time_out(new_time_out)
+    }
+
+    /// See [`HibpClient::change_padding`].
+    pub fn change_padding(&mut self, add_padding: bool) {
+        self.inner.change_padding(add_padding);
+    }
+
+    // ╔══════════════════════════════════════════════════════════════════════════════════════════════╗
+    // ║                                                                                              ║
+    // ║                                 ACCOUNT LOOKUP METHODS                                       ║
+    // ║                                                                                              ║
+    // ╚══════════════════════════════════════════════════════════════════════════════════════════════╝
+
+    /// See [`HibpClient::get_account_breach_names`].
+    pub fn get_account_breach_names(&self, email: &str) -> HibpResult<Vec<String>> {
+        self.block_on(|c| c.get_account_breach_names(email))
+    }
+
+    /// See [`HibpClient::get_account_breaches`].
+    pub fn get_account_breaches(&self, email: &str) -> HibpResult<Vec<Breach>> {
+        self.block_on(|c| c.get_account_breaches(email))
+    }
+
+    /// See [`HibpClient::get_account_pastes`].
+    pub fn get_account_pastes(&self, email: &str) -> HibpResult<Vec<Paste>> {
+        self.block_on(|c| c.get_account_pastes(email))
+    }
+
+    // ╔══════════════════════════════════════════════════════════════════════════════════════════════╗
+    // ║                                                                                              ║
+    // ║                                    BREACHES METHODS                                          ║
+    // ║                                                                                              ║
+    // ╚══════════════════════════════════════════════════════════════════════════════════════════════╝
+
+    /// See [`HibpClient::get_breach`].
+    pub fn get_breach(&self, name: &str) -> HibpResult<Breach> {
+        self.block_on(|c| c.get_breach(name))
+    }
+
+    /// See [`HibpClient::get_latest_breach`].
+    pub fn get_latest_breach(&self) -> HibpResult<Breach> {
+        self.block_on(|c| c.get_latest_breach())
+    }
+
+    /// See [`HibpClient::get_domain_breaches`].
+    pub fn get_domain_breaches(
+        &self,
+        domain: &str,
+        matching: DomainMatch,
+    ) -> HibpResult<Vec<Breach>> {
+        self.block_on(|c| c.get_domain_breaches(domain, matching))
+    }
+
+    /// See [`HibpClient::get_all_breaches`].
+    pub fn get_all_breaches(&self) -> HibpResult<Vec<Breach>> {
+        self.block_on(|c| c.get_all_breaches())
+    }
+
+    // ╔══════════════════════════════════════════════════════════════════════════════════════════════╗
+    // ║                                                                                              ║
+    // ║                                        Passwords                                             ║
+    // ║                                                                                              ║
+    // ╚══════════════════════════════════════════════════════════════════════════════════════════════╝
+
+    /// See [`HibpClient::check_password`].
+    pub fn check_password(&self, password: impl AsRef<[u8]>) -> HibpResult<u64> {
+        self.block_on(|c| c.check_password(password))
+    }
+}
@@ -0,0 +1,304 @@
+//! Application settings persisted between launches, stored as JSON in the user's configuration
+//! directory (`%APPDATA%\emicon` on Windows, `~/Library/Application Support/emicon` on macOS and
+//! `$XDG_CONFIG_HOME/emicon` or `~/.config/emicon` elsewhere).
+//!
+//! Missing settings (e.g. in a file written by an older version) fall back to their defaults.
+
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hibp::load_password_hash_list;
+use crate::shared::{
+    bundled_disposable_domains, load_domain_list, write_atomic, BreachOrder, DateStyle,
+    EmiconResult, QuietHours,
+};
+
+/// Name of the settings file inside [`config_dir`].
+const CONFIG_FILE: &str = "config.json";
+
+/// Name of the optional disposable email domain list inside [`config_dir`].
+const DISPOSABLE_DOMAINS_FILE: &str = "disposable_domains.txt";
+
+/// Name of the optional common password list inside [`config_dir`].
+const COMMON_PASSWORDS_FILE: &str = "common_passwords.txt";
+
+/// Persisted application settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Number of breaches rendered at once in breach lists, with a "show more" control for the
+    /// rest. 0 renders every breach.
+    pub max_display: usize,
+    /// Data classes listed first on breaches, in this order (matched case-insensitively).
+    pub emphasized_data_classes: Vec<String>,
+    /// Data classes left out of breaches' data class lists (matched case-insensitively).
+    pub hidden_data_classes: Vec<String>,
+    /// How breach dates are displayed. Exports always use ISO dates.
+    pub date_style: DateStyle,
+    /// Order of the breaches found for an email.
+    pub account_breach_order: BreachOrder,
+    /// Breach fields shown in the window and included in copied JSON.
+    pub visible_fields: BreachFields,
+    /// Masks email addresses (see [`crate::shared::redact_email`]) wherever the window shows or
+    /// exports them and in logged messages, e.g. while sharing the screen. Queries still use the
+    /// real address.
+    pub redact_emails: bool,
+    /// Seconds after which informational notices (e.g. "Copied!" or a rate limit notice) close
+    /// on their own. 0 keeps them open. Errors always stay open until closed.
+    pub notice_dismiss_secs: u64,
+    /// Window size and position when it was last closed, restored on startup. `None` until the
+    /// window has been closed once.
+    pub window: Option<WindowState>,
+    /// Daily period during which new breach alerts are held back until it ends, if any.
+    pub quiet_hours: Option<QuietHours>,
+    /// Number of threads running background requests. 0 uses one per CPU core, which mostly helps
+    /// large batch checks; the default suits interactive use.
+    pub worker_threads: usize,
+}
+
+/// Window geometry, in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowState {
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl WindowState {
+    /// Part of the window (in physical pixels) that must stay on screen horizontally and below the
+    /// top of the screen when restoring, so it can still be grabbed and moved.
+    const MIN_VISIBLE: i32 = 100;
+
+    /// Returns the geometry with the position moved back on screen if the window would be left of
+    /// or above the screen origin (e.g. after a monitor on that side was disconnected).
+    ///
+    /// The window toolkit doesn't report monitor bounds, so a window beyond the right or bottom
+    /// edge can't be detected and is restored where it was.
+    pub fn clamped(self) -> Self {
+        let width = i32::try_from(self.width).unwrap_or(i32::MAX);
+        Self {
+            x: self.x.max(Self::MIN_VISIBLE.min(width) - width),
+            y: self.y.max(0),
+            ..self
+        }
+    }
+}
+
+/// Which optional breach fields are shown (all by default). A breach's name, title and
+/// description are always shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreachFields {
+    pub domain: bool,
+    pub date: bool,
+    pub pwn_count: bool,
+    /// The `Is*` flags (verified, sensitive, malware, ...).
+    pub flags: bool,
+    pub data_classes: bool,
+}
+
+impl Default for BreachFields {
+    fn default() -> Self {
+        Self {
+            domain: true,
+            date: true,
+            pwn_count: true,
+            flags: true,
+            data_classes: true,
+        }
+    }
+}
+
+impl BreachFields {
+    /// Returns the keys of the hidden fields in HIBP's breach JSON, to leave out of exports.
+    pub fn hidden_json_keys(&self) -> Vec<&'static str> {
+        let mut keys = Vec::new();
+        if !self.domain {
+            keys.push("Domain");
+        }
+        if !self.date {
+            keys.extend(["BreachDate", "AddedDate", "ModifiedDate"]);
+        }
+        if !self.pwn_count {
+            keys.push("PwnCount");
+        }
+        if !self.flags {
+            keys.extend([
+                "IsVerified",
+                "IsFabricated",
+                "IsSensitive",
+                "IsRetired",
+                "IsSpamList",
+                "IsMalware",
+                "IsSubscriptionFree",
+                "IsStealerLog",
+            ]);
+        }
+        if !self.data_classes {
+            keys.push("DataClasses");
+        }
+        keys
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_display: 100,
+            emphasized_data_classes: [
+                "Passwords",
+                "Credit cards",
+                "Credit card CVV",
+                "Partial credit card data",
+                "Bank account numbers",
+            ]
+            .map(String::from)
+            .to_vec(),
+            hidden_data_classes: Vec::new(),
+            date_style: DateStyle::default(),
+            account_breach_order: BreachOrder::default(),
+            visible_fields: BreachFields::default(),
+            redact_emails: false,
+            notice_dismiss_secs: 5,
+            window: None,
+            quiet_hours: None,
+            worker_threads: 2,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the settings file, or returns the defaults if there is none yet (or no configuration
+    /// directory could be determined).
+    ///
+    /// # Errors
+    /// - Returns an IO error if the file exists but can't be read
+    /// - Returns a JSON error if the file isn't valid JSON
+    pub fn load() -> EmiconResult<Self> {
+        let Some(path) = config_dir().map(|dir| dir.join(CONFIG_FILE)) else {
+            return Ok(Self::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the settings file, creating the configuration directory if needed.
+    /// Does nothing if no configuration directory could be determined.
+    pub fn save(&self) -> EmiconResult<()> {
+        let Some(dir) = config_dir() else {
+            return Ok(());
+        };
+
+        fs::create_dir_all(&dir)?;
+        write_atomic(
+            &dir.join(CONFIG_FILE),
+            serde_json::to_string_pretty(self)?.as_bytes(),
+        )?;
+        Ok(())
+    }
+
+    /// Applies the data class settings to a breach's data classes: hidden classes are dropped,
+    /// emphasized ones moved to the front in their configured order, the rest kept in order.
+    pub fn arrange_data_classes<'a>(&self, data_classes: &'a [String]) -> Vec<&'a str> {
+        let position =
+            |list: &[String], class: &str| list.iter().position(|c| c.eq_ignore_ascii_case(class));
+
+        let mut shown: Vec<&str> = data_classes
+            .iter()
+            .map(String::as_str)
+            .filter(|class| position(&self.hidden_data_classes, class).is_none())
+            .collect();
+        // Stable sort: emphasized classes by their configured position, then everything else
+        shown.sort_by_key(|class| {
+            position(&self.emphasized_data_classes, class).unwrap_or(usize::MAX)
+        });
+        shown
+    }
+}
+
+/// Returns the disposable email domains to warn about: the user's list in [`config_dir`] if there
+/// is one, the bundled list otherwise (including when the user's list can't be read).
+pub fn disposable_domains() -> HashSet<String> {
+    let Some(path) = config_dir().map(|dir| dir.join(DISPOSABLE_DOMAINS_FILE)) else {
+        return bundled_disposable_domains();
+    };
+
+    match load_domain_list(&path) {
+        Ok(domains) => domains,
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!(
+                    "Failed to read {}, using the bundled list: {e}",
+                    path.display()
+                );
+            }
+            bundled_disposable_domains()
+        }
+    }
+}
+
+/// Returns the user's list of common password hashes in [`config_dir`] (see
+/// [`crate::hibp::load_password_hash_list`]), or `None` if there is none or it can't be read.
+pub fn common_passwords() -> Option<HashMap<String, u64>> {
+    let path = config_dir()?.join(COMMON_PASSWORDS_FILE);
+    match load_password_hash_list(&path) {
+        Ok(list) => Some(list),
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("Failed to read {}: {e}", path.display());
+            }
+            None
+        }
+    }
+}
+
+/// Returns the directory the application's data (e.g. caches) is stored in, or `None` if the
+/// environment doesn't define one. This is `%LOCALAPPDATA%\emicon` on Windows,
+/// `$XDG_DATA_HOME/emicon` or `~/.local/share/emicon` on Linux, and [`config_dir`] on macOS.
+pub fn data_dir() -> Option<PathBuf> {
+    let env_dir = |var: &str| {
+        std::env::var_os(var)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+
+    if cfg!(windows) {
+        Some(env_dir("LOCALAPPDATA")?.join("emicon"))
+    } else if cfg!(target_os = "macos") {
+        config_dir()
+    } else {
+        let base = env_dir("XDG_DATA_HOME")
+            .or_else(|| env_dir("HOME").map(|home| home.join(".local/share")))?;
+        Some(base.join("emicon"))
+    }
+}
+
+/// Returns the directory the application's settings are stored in, or `None` if the environment
+/// doesn't define one (e.g. no `HOME`).
+pub fn config_dir() -> Option<PathBuf> {
+    let env_dir = |var: &str| {
+        std::env::var_os(var)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+
+    let base = if cfg!(windows) {
+        env_dir("APPDATA")?
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME")?.join("Library/Application Support")
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))?
+    };
+
+    Some(base.join("emicon"))
+}
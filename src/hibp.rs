@@ -12,6 +12,11 @@
 //! - Fetch the full list of breach datasets
 //! - Verify whether a password has appeared in breaches (using k-Anonymity hashing)
 //!
+//! ## TLS Backends
+//! The TLS backend of the underlying `reqwest::Client` is picked with cargo features:
+//! - `native-tls` (default): the platform's TLS library.
+//! - `rustls-tls`: pure Rust TLS, for static or musl builds. Takes precedence when both are enabled.
+//!
 //! ## Example Usage
 //! ```
 //! use hibp_client::HibpClient;
@@ -52,10 +57,16 @@
 
 #![allow(dead_code)]
 
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use futures_util::{Stream, StreamExt};
 use reqwest::{Client, Response, StatusCode};
 use serde::{Deserialize, de::DeserializeOwned, Serialize};
 use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use crate::rate_limiter::TokenBucket;
 
 /// Wrapper type used for all results returned by this crate
 pub type HibpResult<T> = std::result::Result<T, HibpError>;
@@ -71,30 +82,71 @@ pub enum HibpError {
     #[error("queried value does not match any HIBP entry")]
     NotFound,
 
-    /// Response status `429`. Try again later.
+    /// Response status `429`. Try again later. Holds how long to wait before retrying when the
+    /// response says so (`Retry-After`).
     #[error("Rate limited - too many requests")]
-    RateLimit,
+    RateLimit {
+        retry_after: Option<std::time::Duration>,
+    },
 
     /// Response status `401`. Missing or invalid API key.
     #[error("Unauthorized - missing or invalid API key")]
     Unauthorized,
 
-    /// Response status `403`. Request rejected (likely missing or banned User-Agent).
-    #[error("Forbidden - request rejected (likely missing or banned User-Agent)")]
+    /// Response status `403`. Request rejected for a reason other than the User-Agent.
+    #[error("Forbidden - request rejected")]
     Forbidden,
 
+    /// Response status `403` because of the User-Agent (missing, or banned as too generic).
+    #[error("Forbidden - User-Agent rejected, set a descriptive one (e.g. \"my-app/1.0\")")]
+    BannedUserAgent,
+
     /// Response status `400`. Bad request (invalid email format).
     #[error("Bad request - invalid email format")]
     BadRequest,
 
-    /// Response status `503`. Service unavailable (usually upstream or Cloudflare).
+    /// Response status `503`. Service unavailable (usually upstream or Cloudflare). Holds how long
+    /// to wait before retrying when the response says so (`Retry-After`).
     #[error("Service unavailable")]
-    ServiceUnavailable,
+    ServiceUnavailable {
+        retry_after: Option<std::time::Duration>,
+    },
+
+    /// Response status `503` during planned maintenance (the body mentions it). Holds how long
+    /// to wait before retrying when the response says so (`Retry-After`).
+    #[error("HIBP is under maintenance")]
+    Maintenance {
+        retry_after: Option<std::time::Duration>,
+    },
+
+    /// The request didn't complete within the overall deadline (see [`HibpClient::deadline`]).
+    #[error("Request timed out")]
+    Timeout,
+
+    /// The response body isn't the expected JSON.
+    #[error("Response parsing error: {0}")]
+    ParseError(String),
+
+    /// A password hash passed in isn't a SHA1 hex digest (40 hex chars).
+    #[error("Invalid SHA1 hash: {0:?}")]
+    InvalidHash(String),
+
+    /// The response isn't of the expected content type (e.g. a logo URL that doesn't serve an image).
+    #[error("Unexpected content type: {0}")]
+    UnexpectedContentType(String),
 
     /// Error requesting data.
     #[error("Request error: {0}")]
     ReqwestError(#[from] reqwest::Error),
 
+    /// A batch operation used up its retry budget; holds the error of the last failed attempt.
+    #[error("Retry budget exhausted, giving up on the batch. Last error: {0}")]
+    RetryBudgetExhausted(Box<HibpError>),
+
+    /// Two observations compared as the same breach have different names.
+    #[error("Can't compare different breaches: {0} and {1}")]
+    BreachMismatch(String, String),
+
     /// Unknown response status code.
     #[error("Unexpected API response: {status}, body: {body}")]
     Unknown {
@@ -103,6 +155,48 @@ pub enum HibpError {
     },
 }
 
+impl HibpError {
+    /// Returns true for failures that may go away on their own, so the same request is worth
+    /// retrying later: rate limiting, outages (`503` and other `5xx` statuses), timeouts and
+    /// connection failures. Batch operations retry exactly these.
+    ///
+    /// Everything else is terminal and fails the same way on retry: bad or missing API keys,
+    /// rejected requests, invalid input, missing entries, unexpected responses, and an exhausted
+    /// retry budget.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            HibpError::RateLimit { .. }
+            | HibpError::ServiceUnavailable { .. }
+            | HibpError::Maintenance { .. }
+            | HibpError::Timeout => true,
+            HibpError::ReqwestError(e) => e.is_timeout() || e.is_connect(),
+            HibpError::Unknown { status, .. } => status.is_server_error(),
+            HibpError::ClientBuildError(_)
+            | HibpError::NotFound
+            | HibpError::Unauthorized
+            | HibpError::Forbidden
+            | HibpError::BannedUserAgent
+            | HibpError::BadRequest
+            | HibpError::ParseError(_)
+            | HibpError::InvalidHash(_)
+            | HibpError::UnexpectedContentType(_)
+            | HibpError::RetryBudgetExhausted(_)
+            | HibpError::BreachMismatch(..) => false,
+        }
+    }
+
+    /// Returns how long the response asked to wait before retrying (`Retry-After`, capped at
+    /// [`crate::shared::DEFAULT_MAX_RETRY_AFTER`]), if it did.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            HibpError::RateLimit { retry_after }
+            | HibpError::ServiceUnavailable { retry_after }
+            | HibpError::Maintenance { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
 /// Detailed information about a specific data breach.
 /// Returned when querying `check_account_breaches`, `get_breach`, or `get_all_breaches`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -145,6 +239,7 @@ pub struct Breach {
     pub is_spam_list: bool,
     #[serde(rename = "IsMalware")]
     pub is_malware: bool,
+    /// Whether the breach can be searched for an account without a subscription.
     #[serde(rename = "IsSubscriptionFree")]
     pub is_subscription_free: bool,
     #[serde(rename = "IsStealerLog")]
@@ -156,6 +251,50 @@ pub struct Breach {
     pub attribution: Option<String>,
 }
 
+impl Breach {
+    /// Returns a one-paragraph, human readable summary of the breach: title, domain, date, number
+    /// of accounts, the first few data classes, flags (see [`active_flags`]) and the description
+    /// as plain text.
+    pub fn summary(&self) -> String {
+        const SHOWN_DATA_CLASSES: usize = 5;
+
+        let mut summary = self.title.clone();
+        if !self.domain.trim().is_empty() {
+            summary.push_str(&format!(" ({})", self.domain));
+        }
+        summary.push_str(&format!(
+            ", breached on {}: {} accounts.",
+            self.breach_date,
+            crate::shared::format_count(self.pwn_count)
+        ));
+
+        if !self.data_classes.is_empty() {
+            let shown = self.data_classes.len().min(SHOWN_DATA_CLASSES);
+            summary.push_str(&format!(
+                " Exposed: {}",
+                self.data_classes[..shown].join(", ")
+            ));
+            if self.data_classes.len() > shown {
+                summary.push_str(&format!(" and {} more", self.data_classes.len() - shown));
+            }
+            summary.push('.');
+        }
+
+        let flags = active_flags(self);
+        if !flags.is_empty() {
+            summary.push_str(&format!(" Flags: {}.", flags.join(", ")));
+        }
+
+        let description = crate::shared::html_to_text(&self.description);
+        if !description.trim().is_empty() {
+            summary.push(' ');
+            summary.push_str(description.trim());
+        }
+
+        summary
+    }
+}
+
 /// Information about a paste (e.g. from Pastebin) containing an email address.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Paste {
@@ -171,6 +310,143 @@ pub struct Paste {
     pub email_count: Option<u64>,
 }
 
+/// Aggregated figures over the pastes an email appeared in.
+/// Returned by [`paste_summary`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PasteSummary {
+    /// Number of pastes.
+    pub paste_count: usize,
+    /// Sum of the pastes `email_count` (missing counts are treated as 0).
+    pub email_count: u64,
+    /// Number of distinct paste sources (e.g. Pastebin).
+    pub source_count: usize,
+}
+
+/// How damaging the exposure of a data class is, from [`Sensitivity::Low`] to [`Sensitivity::High`].
+/// Assigned by [`data_classes_by_sensitivity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sensitivity {
+    /// Contact or profile details that are often public anyway (names, emails, usernames).
+    Low,
+    /// Personal details useful for profiling or social engineering. Also used for data classes
+    /// not in [`SENSITIVITY_TABLE`].
+    Medium,
+    /// Credentials, financial data and government identifiers, enough on their own for account
+    /// takeover, fraud or identity theft.
+    High,
+}
+
+/// An account's breaches and pastes without anything identifying the account, for sharing with
+/// third parties. Returned by [`HibpClient::anonymized_account_report`].
+///
+/// Included: the email's fingerprint ([`crate::shared::email_fingerprint`]), each breach's name,
+/// date and data classes, and each paste's source, date and email count.
+/// Left out: the email address itself, paste IDs and titles (which can name the account or link
+/// to the dump), and every other breach detail.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnonymizedReport {
+    /// Fingerprint of the email the report is about.
+    pub email_fingerprint: String,
+    pub breaches: Vec<AnonymizedBreach>,
+    pub pastes: Vec<AnonymizedPaste>,
+}
+
+/// Proof of an account's exposure that reveals neither the email nor the breach names.
+/// Returned by [`HibpClient::exposure_receipt`].
+///
+/// Hashing scheme, so a receipt can be verified by someone who knows the email:
+/// - `email_fingerprint` is [`crate::shared::email_fingerprint`] of the email: the lowercase hex
+///   SHA-256 of the trimmed, lowercased address.
+/// - Each entry of `breach_hashes` is the lowercase hex SHA-256 of `"{email_fingerprint}:{name}"`,
+///   where `name` is the breach's `Name` exactly as HIBP returns it (e.g. `"Adobe"`).
+///
+/// Salting with the fingerprint keeps the hashes of one receipt from being matched against
+/// another's, but anyone who knows the email can test every breach name HIBP lists.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExposureReceipt {
+    pub email_fingerprint: String,
+    /// Breach hashes, sorted so they don't reveal the order HIBP returned the breaches in.
+    pub breach_hashes: Vec<String>,
+    pub breach_count: usize,
+    /// When the lookup behind the receipt was made.
+    pub generated_at: DateTime<Utc>,
+}
+
+/// A breach in an [`AnonymizedReport`].
+#[derive(Debug, Clone, Serialize)]
+pub struct AnonymizedBreach {
+    pub name: String,
+    pub breach_date: NaiveDate,
+    pub data_classes: Vec<String>,
+}
+
+/// A paste in an [`AnonymizedReport`].
+#[derive(Debug, Clone, Serialize)]
+pub struct AnonymizedPaste {
+    /// The paste service (e.g. Pastebin), if known.
+    pub source: Option<String>,
+    pub date: Option<DateTime<Utc>>,
+    pub email_count: Option<u64>,
+}
+
+/// Outcome of [`HibpClient::lookup_account`]. The breach and paste lookups succeed or fail
+/// independently, so one failing (e.g. the pastes endpoint returning `503`) doesn't hide the
+/// other's results.
+#[derive(Debug)]
+pub struct AccountLookup {
+    pub breaches: HibpResult<Vec<Breach>>,
+    pub pastes: HibpResult<Vec<Paste>>,
+}
+
+/// Outcome of [`HibpClient::check_identity`]. The account lookup and the password check succeed or
+/// fail independently, so a Pwned Passwords outage doesn't hide the breach results.
+#[derive(Debug)]
+pub struct IdentityCheck {
+    pub breaches: HibpResult<Vec<Breach>>,
+    pub password_count: HibpResult<u64>,
+}
+
+/// Outcome of [`HibpClient::dashboard_snapshot`]. Each part is fetched independently, so one
+/// failing (e.g. the subscription without an API key) doesn't hide the others.
+#[derive(Debug)]
+pub struct DashboardSnapshot {
+    pub latest_breach: HibpResult<Breach>,
+    pub subscription: HibpResult<SubscriptionStatus>,
+    pub stats: HibpResult<GlobalStats>,
+}
+
+/// Combined exposure of several email addresses (e.g. one person's accounts).
+/// Returned by [`HibpClient::combined_account_exposure`].
+#[derive(Debug, Default, Clone)]
+pub struct CombinedExposure {
+    /// Every breach any of the emails appears in, once each, sorted by name.
+    pub breaches: Vec<Breach>,
+    /// Every data class exposed by those breaches, once each, sorted alphabetically.
+    pub data_classes: Vec<String>,
+    /// Number of breaches each email appears in, in the order the emails were given.
+    pub per_email: Vec<(String, usize)>,
+}
+
+/// Dataset-wide figures over all breaches loaded in HIBP.
+/// Returned by [`HibpClient::global_stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GlobalStats {
+    /// Number of breaches.
+    pub breach_count: usize,
+    /// Sum of the breaches `pwn_count`.
+    pub pwn_count: u64,
+}
+
+impl GlobalStats {
+    /// Computes the stats over a breach list.
+    pub fn from_breaches(breaches: &[Breach]) -> Self {
+        Self {
+            breach_count: breaches.len(),
+            pwn_count: breaches.iter().map(|b| b.pwn_count).sum(),
+        }
+    }
+}
+
 /// Information about the subscription status (e.g. rate limit). 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SubscriptionStatus {
@@ -189,6 +465,55 @@ pub struct SubscriptionStatus {
     pub includes_stealer_logs: Option<bool>,
 }
 
+impl SubscriptionStatus {
+    /// Returns the subscription's rate limit as requests per second.
+    pub fn requests_per_second(&self) -> f64 {
+        self.rpm as f64 / 60.0
+    }
+
+    /// Returns a `(capacity, refill_rate)` pair for a [`crate::rate_limiter::TokenBucket`] that
+    /// stays within the subscription's rate limit, or `None` if the limit is below 2 requests per
+    /// minute, which leaves nothing to refill after the first request.
+    ///
+    /// The bucket allows a burst of one second's worth of requests (never less than one) and
+    /// refills at the rest of the limit, so a full bucket plus a minute of refill adds up to
+    /// exactly the per-minute limit.
+    pub fn recommended_bucket(&self) -> Option<(u32, f64)> {
+        let capacity = (self.rpm / 60).max(1);
+        if self.rpm <= capacity {
+            return None;
+        }
+        let refill_rate = (self.rpm - capacity) as f64 / 60.0;
+        Some((capacity.min(u32::MAX as u64) as u32, refill_rate))
+    }
+
+    /// Returns true if HIBP reports a domain size limit for the subscription, which means it
+    /// includes domain search.
+    ///
+    /// False doesn't rule domain search out: the limit is also missing for a key that hasn't run
+    /// a domain search yet (see `domain_search_max_breached_accounts`), so treat false as "not
+    /// known to be entitled" and let the domain search itself (a `401`/`403`) settle it.
+    pub fn can_search_domains(&self) -> bool {
+        self.domain_search_max_breached_accounts.is_some()
+    }
+
+    /// Returns true if the subscription includes stealer log queries (unknown counts as no).
+    pub fn can_query_stealer_logs(&self) -> bool {
+        self.includes_stealer_logs.unwrap_or(false)
+    }
+
+    /// Returns the whole days left until the subscription lapses at `now`. Partial days round
+    /// down, so a subscription that has already expired (even by a minute) gives a negative count.
+    pub fn days_until_expiry(&self, now: DateTime<Utc>) -> i64 {
+        (self.sub_until - now).num_seconds().div_euclid(86_400)
+    }
+
+    /// Returns true if the subscription lapses within `threshold_days` of `now`, or already has.
+    pub fn is_expiring_soon(&self, now: DateTime<Utc>, threshold_days: i64) -> bool {
+        self.days_until_expiry(now) < threshold_days
+    }
+}
+
 /// Information about the subscribed domain (e.g. pawn count).
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SubscribedDomain {
@@ -204,16 +529,225 @@ pub struct SubscribedDomain {
     pub next_subscription_renewal: Option<DateTime<Utc>>,
 }
 
+/// Changes between two fetches of the subscribed domains.
+/// Returned by [`diff_subscribed_domains`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DomainChanges {
+    /// Domains whose `pwn_count` increased, with the increase, in the newer list's order.
+    pub grown: Vec<(String, u64)>,
+    /// Domains only in the newer list, in its order.
+    pub added: Vec<String>,
+    /// Domains only in the older list, in its order.
+    pub removed: Vec<String>,
+}
+
+/// How [`HibpClient::get_domain_breaches`] matches a breach's domain.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DomainMatch {
+    /// Only breaches of exactly this domain (HIBP's own `?domain=` filter).
+    #[default]
+    Exact,
+    /// Breaches of the domain and any of its subdomains (e.g. `mail.acme.com` for `acme.com`).
+    /// This downloads the full breach list and filters it locally, which is a much bigger response.
+    IncludeSubdomains,
+}
+
+/// What account lookups return when HIBP has no data for the account (`404`).
+/// Set with [`HibpClient::change_not_found_policy`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NotFoundPolicy {
+    /// Return [`HibpError::NotFound`].
+    #[default]
+    Error,
+    /// Return an empty collection.
+    Empty,
+}
+
+/// Options for batch password checks such as [`HibpClient::check_password_group`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordCheckOptions {
+    /// Whether range queries ask HIBP to pad the response (enabled by default), regardless of
+    /// [`HibpClient::change_padding`].
+    ///
+    /// Padding hides which ranges were queried, and so which passwords were checked, from anyone
+    /// observing the encrypted traffic (response sizes differ per range otherwise). Disabling it
+    /// makes large batches noticeably faster to download, so only do so when the passwords aren't
+    /// secret anyway, e.g. when checking a dump that's already public.
+    pub add_padding: bool,
+    /// Retries of transient failures allowed across the whole batch.
+    pub retry_budget: u32,
+    /// Most distinct ranges (hash prefixes) to fetch, `None` for no limit. Passwords in the ranges
+    /// left out are reported as not checked. Retries don't count towards the limit.
+    pub max_requests: Option<usize>,
+}
+
+impl Default for PasswordCheckOptions {
+    fn default() -> Self {
+        Self {
+            add_padding: true,
+            retry_budget: 3,
+            max_requests: None,
+        }
+    }
+}
+
+/// Outcome of a batch password check ([`HibpClient::check_password_group`]).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PasswordGroupReport {
+    /// `(password, count)` pairs in input order. The count is `None` for passwords that weren't
+    /// checked because [`PasswordCheckOptions::max_requests`] was reached.
+    pub counts: Vec<(String, Option<u64>)>,
+    /// Number of passwords left unchecked.
+    pub skipped: usize,
+}
+
+/// A password's breach counts by hash type. Returned by [`HibpClient::check_password_all_modes`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordModes {
+    /// Appearances of the password's SHA1 hash, the count [`HibpClient::check_password`] returns.
+    pub sha1_count: u64,
+    /// Appearances of the password's NTLM hash (see [`HibpClient::check_password_ntlm`]).
+    pub ntlm_count: u64,
+}
+
+/// Largest accepted client timeout, in seconds.
+pub const MAX_TIME_OUT: u64 = 300;
+
+/// Settings the underlying `reqwest::Client` is built from.
+#[derive(Debug, Clone)]
+struct ClientConfig {
+    /// Request timeout in seconds.
+    time_out: u64,
+    /// Timeout in seconds for password range queries (`None` uses `time_out`).
+    password_time_out: Option<u64>,
+    /// Timeout in seconds for full breach list downloads (`None` uses `time_out`).
+    breach_list_time_out: Option<u64>,
+    /// Maximum idle connections kept open per host (reqwest's default is unlimited).
+    pool_max_idle_per_host: usize,
+    /// TCP keep-alive interval for open connections (`None` leaves it to the OS).
+    tcp_keepalive: Option<std::time::Duration>,
+    /// Whether password range queries go over HTTP/2 without negotiation (see
+    /// [`HibpClient::change_prefer_http2`]).
+    prefer_http2: bool,
+    /// Whether invalid TLS certificates are accepted (see
+    /// [`HibpClient::change_danger_accept_invalid_certs`]).
+    #[cfg(feature = "dangerous-testing")]
+    accept_invalid_certs: bool,
+}
+
+impl ClientConfig {
+    /// Timeout applied to password range queries.
+    fn password_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.password_time_out.unwrap_or(self.time_out))
+    }
+
+    /// Timeout applied to full breach list downloads.
+    fn breach_list_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.breach_list_time_out.unwrap_or(self.time_out))
+    }
+}
+
+/// Number of password ranges [`HibpClient::check_password_group`] fetches at once.
+const RANGE_CONCURRENCY: usize = 8;
+
+/// Appended to client build errors caused by the TLS setup.
+const TLS_FEATURE_HINT: &str = "Build with the `native-tls` (default) or `rustls-tls` feature; \
+    for static builds without OpenSSL, use `--no-default-features --features rustls-tls`.";
+
+/// Maps a `reqwest::Client` build failure to [`HibpError::ClientBuildError`]. Failures coming from
+/// the TLS backend (e.g. the platform's TLS library missing at runtime) get a hint about the TLS
+/// feature flags, as reqwest's own message rarely points there.
+fn client_build_error(e: reqwest::Error) -> HibpError {
+    let message = e.to_string();
+    let mut source = std::error::Error::source(&e);
+    let mut is_tls = message.to_ascii_lowercase().contains("tls");
+    while let Some(cause) = source {
+        is_tls |= cause.to_string().to_ascii_lowercase().contains("tls");
+        source = cause.source();
+    }
+
+    if is_tls {
+        HibpError::ClientBuildError(format!("{message}. {TLS_FEATURE_HINT}"))
+    } else {
+        HibpError::ClientBuildError(message)
+    }
+}
+
+/// Shortest interval [`HibpClient::start_background_refresh`] and
+/// [`HibpClient::start_latest_breach_monitor`] poll at; shorter ones are raised to it.
+const MIN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Delay before a batch operation retries a failed request, unless the response said how long to
+/// wait (see [`HibpError::retry_after`]).
+const RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Returns the current API key, e.g. from the OS keyring (see [`HibpClient::change_auto_refresh_key`]).
+pub type KeySource = Arc<dyn Fn() -> Option<String> + Send + Sync>;
+
+/// A downloaded breach logo: its bytes and `Content-Type`.
+type Logo = (Vec<u8>, Option<String>);
+
+/// An entry of a truncated account breach list, which only carries the breach name.
+#[derive(Deserialize)]
+struct BreachName {
+    #[serde(rename = "Name")]
+    name: String,
+}
+
+/// In-memory copy of the full breach list.
+#[derive(Debug, Default)]
+struct BreachCache {
+    breaches: Option<Vec<Breach>>,
+    /// `ETag` of the response the breaches came from, used to re-fetch conditionally.
+    etag: Option<String>,
+}
+
 /// Client for accessing the HIBP API.
 /// Create an instance using [`HibpClient::new`], supplying:
 /// - A user-Agent string
 /// - Timeout duration
 pub struct HibpClient {
     client: Client,
+    /// Client for the Pwned Passwords host when HTTP/2 is preferred, `None` to use `client`.
+    password_client: Option<Client>,
     user_agent: String,
     pub api_key: String,
+    /// Where to reload the API key from when it's rejected, if auto refresh is enabled.
+    key_source: Option<KeySource>,
+    /// Key reloaded from `key_source`, used instead of `api_key` until the key is changed.
+    refreshed_api_key: Mutex<Option<String>>,
+    config: ClientConfig,
+    not_found_policy: NotFoundPolicy,
+    /// Whether password range queries ask HIBP to pad the response (see [`HibpClient::change_padding`]).
+    add_padding: bool,
+    /// Local list of common password hashes consulted before the API (see
+    /// [`HibpClient::change_common_passwords`]), uppercase SHA1 hex to count.
+    common_passwords: Option<HashMap<String, u64>>,
+    /// Logos already fetched by [`HibpClient::fetch_logo_with_type`], keyed by URL.
+    logo_cache: Mutex<HashMap<String, Logo>>,
+    /// Breaches already fetched by [`HibpClient::get_breach`], keyed by name.
+    breach_details_cache: Mutex<HashMap<String, Breach>>,
+    /// Last full breach list fetched, shared with background refresh tasks.
+    breach_cache: Arc<Mutex<BreachCache>>,
+    /// Throttles authenticated requests, if set (see [`HibpClient::change_rate_limiter`]).
+    rate_limiter: Option<TokenBucket>,
+    /// Requests per minute allowed by the subscription, once fetched by
+    /// [`HibpClient::get_subscription`].
+    subscription_rpm: Mutex<Option<u64>>,
+    /// Directory account breach results are cached in and how long they stay fresh, if enabled
+    /// (see [`HibpClient::change_account_cache`]).
+    account_cache: Option<(std::path::PathBuf, std::time::Duration)>,
+    /// How long a password range request is shared with identical ones, if enabled (see
+    /// [`HibpClient::change_range_dedup`]).
+    range_dedup_window: Option<std::time::Duration>,
+    /// Recent range requests by hash prefix, with when they started.
+    recent_ranges: Mutex<HashMap<String, (std::time::Instant, Arc<RangeCell>)>>,
 }
 
+/// Response body of a deduplicated range request, shared by every caller asking for the same
+/// prefix within the window.
+type RangeCell = tokio::sync::OnceCell<String>;
+
 impl HibpClient {
     // ╔══════════════════════════════════════════════════════════════════════════════════════════════╗
     // ║                                                                                              ║
@@ -223,9 +757,13 @@ impl HibpClient {
 
     /// Creates a new HIBP client.
     ///
+    /// `time_out` is the per-request timeout in seconds, between 1 and [`MAX_TIME_OUT`].
+    /// 0 is rejected rather than read as "no timeout", which would let a request hang forever.
+    ///
     /// # Errors
     /// - Returns [`HibpError::ClientBuildError`] if the `reqwest::Client` fails to build
     /// - Returns [`HibpError::ClientBuildError`] if the User-Agent is empty
+    /// - Returns [`HibpError::ClientBuildError`] if `time_out` is 0 or above [`MAX_TIME_OUT`]
     pub fn new(user_agent: String, time_out: u64) -> HibpResult<Self> {
         if user_agent.trim().is_empty() {
             return Err(HibpError::ClientBuildError(
@@ -233,28 +771,88 @@ impl HibpClient {
             ));
         }
 
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(time_out))
-            .build()
-            .map_err(|e| HibpError::ClientBuildError(e.to_string()))?;
+        let config = ClientConfig {
+            time_out,
+            password_time_out: None,
+            breach_list_time_out: None,
+            pool_max_idle_per_host: usize::MAX,
+            tcp_keepalive: None,
+            prefer_http2: false,
+            #[cfg(feature = "dangerous-testing")]
+            accept_invalid_certs: false,
+        };
+        let client = Self::build_client(&config)?;
 
         Ok(Self {
             client,
+            password_client: None,
             user_agent,
             api_key: "00000000000000000000000000000000".to_string(),
+            key_source: None,
+            refreshed_api_key: Mutex::new(None),
+            config,
+            not_found_policy: NotFoundPolicy::Error,
+            add_padding: true,
+            common_passwords: None,
+            logo_cache: Mutex::new(HashMap::new()),
+            breach_details_cache: Mutex::new(HashMap::new()),
+            breach_cache: Arc::new(Mutex::new(BreachCache::default())),
+            rate_limiter: None,
+            subscription_rpm: Mutex::new(None),
+            account_cache: None,
+            range_dedup_window: None,
+            recent_ranges: Mutex::new(HashMap::new()),
         })
     }
 
     /// Update the subscription status for the API key.
     pub async fn get_subscription(&self) -> HibpResult<SubscriptionStatus> {
         let url = "https://haveibeenpwned.com/api/v3/subscription/status";
-        Ok(self.request(url).await?)
+        let status: SubscriptionStatus = self.request(url).await?;
+        *self.subscription_rpm.lock().unwrap() = Some(status.rpm);
+        Ok(status)
+    }
+
+    /// Fetches the subscription status of several API keys concurrently, one client per key.
+    /// Returns each key with its status, or the error that key ran into (e.g.
+    /// [`HibpError::Unauthorized`] for an invalid key), in input order.
+    pub async fn validate_keys(
+        keys: &[String],
+        user_agent: &str,
+        time_out: u64,
+    ) -> Vec<(String, HibpResult<SubscriptionStatus>)> {
+        let checks = keys.iter().map(|key| async move {
+            let status = match Self::new(user_agent.to_string(), time_out) {
+                Ok(mut client) => {
+                    client.change_api_key(key.clone());
+                    client.get_subscription().await
+                }
+                Err(e) => Err(e),
+            };
+            (key.clone(), status)
+        });
+
+        futures_util::future::join_all(checks).await
     }
 
     /// Get all domains subscribed (verified) under the API key.
     pub async fn get_subscribed_domains(&self) -> HibpResult<Vec<SubscribedDomain>> {
         let url = "https://haveibeenpwned.com/api/v3/subscribeddomains";
-        Ok(self.request(url).await?)
+        self.request(url).await
+    }
+
+    /// Returns true if `domain` is one of the API key's verified domains (see
+    /// [`HibpClient::get_subscribed_domains`]), which domain search requires.
+    /// Domains are compared case-insensitively, ignoring surrounding whitespace and a trailing dot.
+    pub async fn is_domain_verified(&self, domain: &str) -> HibpResult<bool> {
+        let normalize = |domain: &str| domain.trim().trim_end_matches('.').to_ascii_lowercase();
+        let domain = normalize(domain);
+
+        Ok(self
+            .get_subscribed_domains()
+            .await?
+            .iter()
+            .any(|d| normalize(&d.domain_name) == domain))
     }
 
     /// Updates the API key and subscription.
@@ -265,15 +863,289 @@ impl HibpClient {
         } else {
             self.api_key = new_api_key;
         }
+        *self.refreshed_api_key.get_mut().unwrap() = None;
+    }
+
+    /// Enables (`Some`) or disables (`None`, the default) reloading the API key when it's rejected.
+    ///
+    /// When an authenticated request returns `401`, `source` is asked for the current key (e.g.
+    /// from the OS keyring, in case it was rotated elsewhere). If it returns a different key, the
+    /// request is retried once with it, and the new key is used from then on.
+    pub fn change_auto_refresh_key(&mut self, source: Option<KeySource>) {
+        self.key_source = source;
     }
 
     /// Updates the client timeout duration (Builds a new client, should be fine since HIBP is stateless).
+    /// Accepts the same range as [`HibpClient::new`]; the current client is kept on error.
     pub fn change_time_out(&mut self, new_time_out: u64) -> HibpResult<()> {
-        self.client = Client::builder()
-            .timeout(std::time::Duration::from_secs(new_time_out))
-            .build()
-            .map_err(|e| HibpError::ClientBuildError(e.to_string()))?;
-        Ok(())
+        self.rebuild_client(ClientConfig {
+            time_out: new_time_out,
+            ..self.config.clone()
+        })
+    }
+
+    /// Sets a timeout in seconds for password range queries alone, which are small and usually
+    /// fast, so a shorter timeout than the client's fails a stalled check sooner.
+    /// `None` (the default) uses the client timeout. Accepts the same range as [`HibpClient::new`].
+    pub fn change_password_time_out(&mut self, time_out: Option<u64>) -> HibpResult<()> {
+        self.rebuild_client(ClientConfig {
+            password_time_out: time_out,
+            ..self.config.clone()
+        })
+    }
+
+    /// Sets a timeout in seconds for full breach list downloads alone (several MB), so they can be
+    /// given longer than other calls. `None` (the default) uses the client timeout.
+    /// Accepts the same range as [`HibpClient::new`].
+    pub fn change_breach_list_time_out(&mut self, time_out: Option<u64>) -> HibpResult<()> {
+        self.rebuild_client(ClientConfig {
+            breach_list_time_out: time_out,
+            ..self.config.clone()
+        })
+    }
+
+    /// Updates the maximum number of idle connections kept open per host (unlimited by default).
+    ///
+    /// Batch jobs firing many requests at the same host reuse pooled connections instead of
+    /// opening new ones; a cap around 8-32 keeps the pool useful without holding sockets forever.
+    pub fn change_pool_max_idle_per_host(&mut self, max_idle: usize) -> HibpResult<()> {
+        self.rebuild_client(ClientConfig {
+            pool_max_idle_per_host: max_idle,
+            ..self.config.clone()
+        })
+    }
+
+    /// Updates the TCP keep-alive interval of open connections (`None`, the default, leaves it to
+    /// the OS). Around 60 seconds keeps pooled connections alive between batches.
+    pub fn change_tcp_keepalive(
+        &mut self,
+        keepalive: Option<std::time::Duration>,
+    ) -> HibpResult<()> {
+        self.rebuild_client(ClientConfig {
+            tcp_keepalive: keepalive,
+            ..self.config.clone()
+        })
+    }
+
+    /// Sends password range queries over HTTP/2 with prior knowledge instead of negotiating the
+    /// protocol (disabled by default).
+    ///
+    /// The concurrent range fetches of [`HibpClient::check_password_group`] are then multiplexed
+    /// over a single connection rather than opening one TLS connection each. Only the Pwned
+    /// Passwords host is affected; the main API keeps the default negotiation.
+    ///
+    /// The saving is in connection setup, not transfer: without it, a batch's first concurrent
+    /// fetches each pay a TCP and TLS handshake (typically a few round trips apiece) before HTTP/2
+    /// is negotiated, while with it they all wait on a single one. Once connections are pooled,
+    /// both settings download ranges equally fast, so small batches on high-latency links benefit
+    /// most and long batches barely change. No figures are given because the gain is set by the
+    /// round-trip time to the host rather than by this client.
+    pub fn change_prefer_http2(&mut self, prefer: bool) -> HibpResult<()> {
+        self.rebuild_client(ClientConfig {
+            prefer_http2: prefer,
+            ..self.config.clone()
+        })
+    }
+
+    /// **Dangerous, for testing only.** Accepts any TLS certificate, including self-signed and
+    /// expired ones, when `accept` is true (false by default). Only available with the
+    /// `dangerous-testing` feature, to test against a local mock server over HTTPS.
+    ///
+    /// With this enabled, anyone on the network path can impersonate HIBP, read the API key and
+    /// queried emails, and forge responses. Never enable it outside tests; a warning is printed
+    /// whenever it is.
+    #[cfg(feature = "dangerous-testing")]
+    pub fn change_danger_accept_invalid_certs(&mut self, accept: bool) -> HibpResult<()> {
+        if accept {
+            eprintln!("WARNING: TLS certificate verification is disabled; use only for testing");
+        }
+        self.rebuild_client(ClientConfig {
+            accept_invalid_certs: accept,
+            ..self.config.clone()
+        })
+    }
+
+    /// Sets (`Some`) or removes (`None`, the default) the token bucket authenticated requests wait
+    /// on before being sent. [`SubscriptionStatus::recommended_bucket`] gives settings that stay
+    /// within the subscription's rate limit.
+    pub fn change_rate_limiter(&mut self, limiter: Option<TokenBucket>) {
+        self.rate_limiter = limiter;
+    }
+
+    /// Estimates how many more authenticated requests can be sent this minute: the subscription's
+    /// requests per minute, minus the requests the rate limiter let through in the last minute.
+    ///
+    /// Returns `None` until [`HibpClient::get_subscription`] has succeeded, or without a rate
+    /// limiter (see [`HibpClient::change_rate_limiter`]), since requests aren't counted then.
+    pub async fn remaining_budget_this_minute(&self) -> Option<u32> {
+        let rpm = (*self.subscription_rpm.lock().unwrap())?;
+        let used = self.rate_limiter.as_ref()?.consumed_last_minute().await;
+        Some(rpm.saturating_sub(used as u64).min(u32::MAX as u64) as u32)
+    }
+
+    /// Overall deadline for a single call: the longest configured timeout plus a small buffer for
+    /// what the timeout doesn't cover well (e.g. a DNS stall). Follows [`HibpClient::change_time_out`]
+    /// and the per-call timeouts.
+    pub fn deadline(&self) -> std::time::Duration {
+        let longest = self
+            .config
+            .password_timeout()
+            .max(self.config.breach_list_timeout())
+            .max(std::time::Duration::from_secs(self.config.time_out));
+        longest + std::time::Duration::from_secs(5)
+    }
+
+    /// Enables or disables the `Add-Padding` header on password range queries (enabled by default).
+    ///
+    /// Padding hides the real size of the response from on-path observers by adding synthetic
+    /// entries with a count of 0. Disabling it makes responses smaller but less private.
+    pub fn change_padding(&mut self, add_padding: bool) {
+        self.add_padding = add_padding;
+        self.recent_ranges.get_mut().unwrap().clear();
+    }
+
+    /// Enables (`Some`) or disables (`None`, the default) deduplication of password range
+    /// requests: checks whose hashes share a prefix within `window` of each other (e.g. a check
+    /// button pressed repeatedly, 500ms is a good window) share one request and its response.
+    ///
+    /// Only the 5 character prefix and the range body are kept, never the password or its full
+    /// hash. A failed request isn't shared; the next caller tries again. Leave it disabled where
+    /// every check must reflect a fresh response.
+    pub fn change_range_dedup(&mut self, window: Option<std::time::Duration>) {
+        self.range_dedup_window = window;
+        self.recent_ranges.get_mut().unwrap().clear();
+    }
+
+    /// Sets (`Some`) or removes (`None`, the default) a local list of common password hashes with
+    /// their counts, as [`load_password_hash_list`] reads it. [`HibpClient::check_password`]
+    /// looks passwords up there first and returns the listed count without a network call, which
+    /// also works offline. A password missing from the list may still be breached, so a miss
+    /// always goes on to query HIBP.
+    pub fn change_common_passwords(&mut self, list: Option<HashMap<String, u64>>) {
+        self.common_passwords = list;
+    }
+
+    /// Updates what account lookups return for an account HIBP has no data on
+    /// ([`NotFoundPolicy::Error`] by default).
+    pub fn change_not_found_policy(&mut self, policy: NotFoundPolicy) {
+        self.not_found_policy = policy;
+    }
+
+    /// Enables caching [`HibpClient::get_account_breaches`] results in `dir` (e.g. under
+    /// [`crate::config::data_dir`]), or disables it with `None` (the default). Cached results are
+    /// returned without querying HIBP until they're older than `ttl`, which saves rate limit budget
+    /// when the same email is looked up repeatedly.
+    ///
+    /// Cache files are named after the email's fingerprint ([`crate::shared::email_fingerprint`]),
+    /// so the directory never holds email addresses in plain text. The breaches themselves are
+    /// stored as they are.
+    pub fn change_account_cache(
+        &mut self,
+        dir: Option<std::path::PathBuf>,
+        ttl: std::time::Duration,
+    ) {
+        self.account_cache = dir.map(|dir| (dir, ttl));
+    }
+
+    /// Starts a task that re-syncs the breach cache every `interval` (starting immediately), so
+    /// [`HibpClient::get_all_breaches_cached`] stays fast and current in long-running sessions.
+    ///
+    /// Each sync is a single conditional request; an unchanged list costs a `304` and no download.
+    /// A failed sync is logged and retried at the next interval. An `interval` under a minute
+    /// (including zero) is raised to a minute. The task stops when the returned handle is stopped
+    /// or dropped. Must be called from within a Tokio runtime.
+    pub fn start_background_refresh(&self, interval: std::time::Duration) -> BackgroundRefresh {
+        let interval = interval.max(MIN_POLL_INTERVAL);
+        let sync = self.breach_sync();
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = sync.run().await {
+                    eprintln!("Background breach refresh failed: {e}");
+                }
+            }
+        });
+
+        BackgroundRefresh { task }
+    }
+
+    /// Starts a task that polls the latest breach about every `interval` and calls `on_new` when
+    /// it changes (a different name than the last one seen).
+    ///
+    /// Each wait is `interval` plus or minus up to 10%, so several monitors don't poll in step.
+    /// With `last_seen_file`, the last seen breach name is kept there so a restart doesn't alert
+    /// again; without it (or on the very first run) the first breach fetched is only remembered.
+    /// A failed poll is logged and retried at the next interval. An `interval` under a minute
+    /// (including zero) is raised to a minute. The task stops when the returned handle is stopped
+    /// or dropped. Must be called from within a Tokio runtime.
+    ///
+    /// During `quiet_hours`, polling goes on but new breaches are queued instead of passed to
+    /// `on_new`; they're delivered in order at the first poll after the quiet period ends. The
+    /// last seen name is only saved once they are, so a restart in between still alerts.
+    pub fn start_latest_breach_monitor(
+        &self,
+        interval: std::time::Duration,
+        last_seen_file: Option<std::path::PathBuf>,
+        quiet_hours: Option<crate::shared::QuietHours>,
+        on_new: impl Fn(Breach) + Send + 'static,
+    ) -> BackgroundRefresh {
+        let interval = interval.max(MIN_POLL_INTERVAL);
+        let client = self.client.clone();
+        let user_agent = self.user_agent.clone();
+        let task = tokio::spawn(async move {
+            let mut last_seen = match &last_seen_file {
+                Some(path) => tokio::fs::read_to_string(path)
+                    .await
+                    .ok()
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty()),
+                None => None,
+            };
+            // New breaches not yet passed to `on_new` because of quiet hours
+            let mut deferred = Vec::new();
+
+            loop {
+                let latest: HibpResult<Breach> = async {
+                    let response = client
+                        .get("https://haveibeenpwned.com/api/v3/latestbreach")
+                        .header("User-Agent", &user_agent)
+                        .send()
+                        .await?;
+                    Ok(Self::handle_response(response).await?.json().await?)
+                }
+                .await;
+
+                match latest {
+                    Ok(breach) if last_seen.as_deref() != Some(breach.name.as_str()) => {
+                        let first_run = last_seen.is_none();
+                        last_seen = Some(breach.name.clone());
+                        if first_run {
+                            save_last_seen(last_seen_file.as_deref(), &breach.name).await;
+                        } else {
+                            deferred.push(breach);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => eprintln!("Latest breach poll failed: {e}"),
+                }
+
+                if !quiet_hours.is_some_and(|quiet| quiet.is_active()) {
+                    if let Some(newest) = deferred.last() {
+                        // Saved only once `on_new` has the breaches, so a restart during quiet
+                        // hours alerts again instead of losing them
+                        let newest = newest.name.clone();
+                        deferred.drain(..).for_each(&on_new);
+                        save_last_seen(last_seen_file.as_deref(), &newest).await;
+                    }
+                }
+
+                tokio::time::sleep(jittered(interval)).await;
+            }
+        });
+
+        BackgroundRefresh { task }
     }
 
     // ╔══════════════════════════════════════════════════════════════════════════════════════════════╗
@@ -282,26 +1154,183 @@ impl HibpClient {
     // ║                                                                                              ║
     // ╚══════════════════════════════════════════════════════════════════════════════════════════════╝
 
+    /// Builds the underlying `reqwest::Client` with the TLS backend selected by the cargo features.
+    fn build_client(config: &ClientConfig) -> HibpResult<Client> {
+        Self::client_builder(config)?
+            .build()
+            .map_err(client_build_error)
+    }
+
+    /// Builds the client used for password range queries, if it differs from the main one.
+    fn build_password_client(config: &ClientConfig) -> HibpResult<Option<Client>> {
+        if !config.prefer_http2 {
+            return Ok(None);
+        }
+
+        Self::client_builder(config)?
+            .http2_prior_knowledge()
+            .build()
+            .map(Some)
+            .map_err(client_build_error)
+    }
+
+    /// Validates `config` and returns a `reqwest::ClientBuilder` with its settings applied.
+    fn client_builder(config: &ClientConfig) -> HibpResult<reqwest::ClientBuilder> {
+        let time_outs = [
+            Some(config.time_out),
+            config.password_time_out,
+            config.breach_list_time_out,
+        ];
+        if time_outs
+            .into_iter()
+            .flatten()
+            .any(|time_out| !(1..=MAX_TIME_OUT).contains(&time_out))
+        {
+            return Err(HibpError::ClientBuildError(format!(
+                "Timeout must be between 1 and {MAX_TIME_OUT} seconds."
+            )));
+        }
+
+        if !cfg!(any(feature = "native-tls", feature = "rustls-tls")) {
+            return Err(HibpError::ClientBuildError(format!(
+                "no TLS backend enabled, so HIBP can't be reached over HTTPS. {TLS_FEATURE_HINT}"
+            )));
+        }
+
+        let builder = Client::builder()
+            .timeout(std::time::Duration::from_secs(config.time_out))
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .tcp_keepalive(config.tcp_keepalive);
+
+        #[cfg(feature = "rustls-tls")]
+        let builder = builder.use_rustls_tls();
+
+        #[cfg(feature = "dangerous-testing")]
+        let builder = builder.danger_accept_invalid_certs(config.accept_invalid_certs);
+
+        Ok(builder)
+    }
+
+    /// Replaces the `reqwest::Client`s with ones built from `config`, keeping the old ones on failure.
+    fn rebuild_client(&mut self, config: ClientConfig) -> HibpResult<()> {
+        let client = Self::build_client(&config)?;
+        self.password_client = Self::build_password_client(&config)?;
+        self.client = client;
+        self.config = config;
+        Ok(())
+    }
+
+    /// Returns the client to send password range queries with.
+    fn password_client(&self) -> &Client {
+        self.password_client.as_ref().unwrap_or(&self.client)
+    }
+
+    /// Returns what's needed to sync the breach cache, detached from `self`.
+    fn breach_sync(&self) -> BreachSync {
+        BreachSync {
+            client: self.client.clone(),
+            user_agent: self.user_agent.clone(),
+            time_out: self.config.breach_list_timeout(),
+            cache: Arc::clone(&self.breach_cache),
+        }
+    }
+
     /// Generic GET request helper that deserializes JSON into type `D`.
     async fn request<D: DeserializeOwned>(&self, url: &str) -> HibpResult<D> {
-        let mut req = self.client.get(url).header("User-Agent", &self.user_agent);
-        req = req.header("hibp-api-key", self.api_key.clone());
-        let response = req.send().await?;
-        let response = self.handle_response(response).await?;
+        let response = self.send_with_key(url, &self.current_api_key()).await?;
+        let response = match Self::handle_response(response).await {
+            // Retry once if the key was rotated since it was set
+            Err(HibpError::Unauthorized) => match self.refresh_api_key() {
+                Some(key) => Self::handle_response(self.send_with_key(url, &key).await?).await?,
+                None => return Err(HibpError::Unauthorized),
+            },
+            response => response?,
+        };
         let parsed: D = response.json::<D>().await?;
         Ok(parsed)
     }
 
+    /// Sends an authenticated GET request with `api_key`, once the rate limiter (if any) allows it.
+    async fn send_with_key(&self, url: &str, api_key: &str) -> HibpResult<Response> {
+        self.send_limited(self.get_with_key(url, api_key)).await
+    }
+
+    /// Builds an authenticated GET request with `api_key`.
+    fn get_with_key(&self, url: &str, api_key: &str) -> reqwest::RequestBuilder {
+        let req = self.client.get(url).header("User-Agent", &self.user_agent);
+        req.header("hibp-api-key", api_key)
+    }
+
+    /// Sends a request once the rate limiter (if any) allows it.
+    async fn send_limited(&self, req: reqwest::RequestBuilder) -> HibpResult<Response> {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.consume().await;
+        }
+        Ok(req.send().await?)
+    }
+
+    /// Returns the API key to send: the reloaded one if any, otherwise `api_key`.
+    fn current_api_key(&self) -> String {
+        self.refreshed_api_key
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| self.api_key.clone())
+    }
+
+    /// Reloads the API key from the key source, returning it if it differs from the current one.
+    fn refresh_api_key(&self) -> Option<String> {
+        let key = (self.key_source.as_ref()?)()?;
+        if key.trim().is_empty() || key == self.current_api_key() {
+            return None;
+        }
+
+        *self.refreshed_api_key.lock().unwrap() = Some(key.clone());
+        Some(key)
+    }
+
+    /// Maps a `404` account lookup to an empty collection under [`NotFoundPolicy::Empty`].
+    fn apply_not_found_policy<T>(&self, result: HibpResult<Vec<T>>) -> HibpResult<Vec<T>> {
+        match result {
+            Err(HibpError::NotFound) if self.not_found_policy == NotFoundPolicy::Empty => {
+                Ok(Vec::new())
+            }
+            result => result,
+        }
+    }
+
     /// Internal helper to process API responses consistently.
-    async fn handle_response(&self, response: Response) -> HibpResult<Response> {
+    async fn handle_response(response: Response) -> HibpResult<Response> {
         match response.status() {
             StatusCode::OK => Ok(response),
             StatusCode::NOT_FOUND => Err(HibpError::NotFound),
-            StatusCode::FORBIDDEN => Err(HibpError::Forbidden),
+            StatusCode::FORBIDDEN => {
+                // HIBP explains a rejected User-Agent in the body
+                let body = response
+                    .text()
+                    .await
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                if body.contains("user agent") || body.contains("user-agent") {
+                    Err(HibpError::BannedUserAgent)
+                } else {
+                    Err(HibpError::Forbidden)
+                }
+            }
             StatusCode::BAD_REQUEST => Err(HibpError::BadRequest),
             StatusCode::UNAUTHORIZED => Err(HibpError::Unauthorized),
-            StatusCode::TOO_MANY_REQUESTS => Err(HibpError::RateLimit),
-            StatusCode::SERVICE_UNAVAILABLE => Err(HibpError::ServiceUnavailable),
+            StatusCode::TOO_MANY_REQUESTS => Err(HibpError::RateLimit {
+                retry_after: Self::retry_after(&response),
+            }),
+            StatusCode::SERVICE_UNAVAILABLE => {
+                let retry_after = Self::retry_after(&response);
+                let body = response.text().await.unwrap_or_default();
+                if body.to_ascii_lowercase().contains("maintenance") {
+                    Err(HibpError::Maintenance { retry_after })
+                } else {
+                    Err(HibpError::ServiceUnavailable { retry_after })
+                }
+            }
             status => {
                 let bytes = response.bytes().await?;
                 let body = String::from_utf8(bytes.to_vec())
@@ -311,6 +1340,16 @@ impl HibpClient {
         }
     }
 
+    /// Reads the response's `Retry-After` header, capped at
+    /// [`crate::shared::DEFAULT_MAX_RETRY_AFTER`].
+    fn retry_after(response: &Response) -> Option<std::time::Duration> {
+        let value = response.headers().get(reqwest::header::RETRY_AFTER)?;
+        crate::shared::parse_retry_after(
+            value.to_str().ok()?,
+            crate::shared::DEFAULT_MAX_RETRY_AFTER,
+        )
+    }
+
     // ╔══════════════════════════════════════════════════════════════════════════════════════════════╗
     // ║                                                                                              ║
     // ║                                 ACCOUNT LOOKUP METHODS                                       ║
@@ -318,30 +1357,282 @@ impl HibpClient {
     // ╚══════════════════════════════════════════════════════════════════════════════════════════════╝
 
     /// Returns a list of breach names for a given email (truncated).
+    /// An unknown email follows the client's [`NotFoundPolicy`].
     pub async fn get_account_breach_names(&self, email: &str) -> HibpResult<Vec<String>> {
         let url = format!(
             "https://haveibeenpwned.com/api/v3/breachedaccount/{}?truncateResponse=true",
-            urlencoding::encode(&email)
+            urlencoding::encode(email)
         );
-        Ok(self.request(&url).await?)
+        let names: HibpResult<Vec<BreachName>> = self.request(&url).await;
+        self.apply_not_found_policy(names.map(|names| names.into_iter().map(|b| b.name).collect()))
     }
 
     /// Returns full breach details (not truncated) for a given email.
+    /// An unknown email follows the client's [`NotFoundPolicy`].
+    ///
+    /// With [`HibpClient::change_account_cache`] enabled, a fresh cached result is returned
+    /// instead of querying HIBP, and new results are cached (best effort).
     pub async fn get_account_breaches(&self, email: &str) -> HibpResult<Vec<Breach>> {
-        let url = format!(
+        let Some((dir, ttl)) = &self.account_cache else {
+            return self.fetch_account_breaches(email).await;
+        };
+
+        let path = dir.join(format!("{}.json", crate::shared::email_fingerprint(email)));
+        if let Some((breaches, true)) = read_json_cache(&path, *ttl).await {
+            return Ok(breaches);
+        }
+
+        let breaches = self.fetch_account_breaches(email).await?;
+        if let Ok(json) = serde_json::to_vec(&breaches) {
+            if tokio::fs::create_dir_all(dir).await.is_ok() {
+                let _ = crate::shared::write_atomic_async(&path, json).await;
+            }
+        }
+        Ok(breaches)
+    }
+
+    /// Queries HIBP for an email's full breach details, bypassing the account cache.
+    async fn fetch_account_breaches(&self, email: &str) -> HibpResult<Vec<Breach>> {
+        let url = format!(
             "https://haveibeenpwned.com/api/v3/breachedaccount/{}?truncateResponse=false",
-            urlencoding::encode(&email)
+            urlencoding::encode(email)
         );
-        Ok(self.request(&url).await?)
+        self.apply_not_found_policy(self.request(&url).await)
+    }
+
+    /// Returns full breach details for a given email like [`HibpClient::get_account_breaches`], but
+    /// fetches only the (much smaller) truncated name list and takes the details from `cache`, a
+    /// full breach list such as [`HibpClient::get_all_breaches_cached`] returns. Falls back to
+    /// [`HibpClient::get_account_breaches`] if any of the names is missing from `cache` (e.g. a
+    /// breach added since it was fetched).
+    /// An unknown email follows the client's [`NotFoundPolicy`].
+    pub async fn get_account_breaches_via_cache(
+        &self,
+        email: &str,
+        cache: &[Breach],
+    ) -> HibpResult<Vec<Breach>> {
+        let names = self.get_account_breach_names(email).await?;
+        let by_name: HashMap<&str, &Breach> = cache.iter().map(|b| (b.name.as_str(), b)).collect();
+
+        let breaches: Option<Vec<Breach>> = names
+            .iter()
+            .map(|name| by_name.get(name.as_str()).map(|&b| b.clone()))
+            .collect();
+        match breaches {
+            Some(breaches) => Ok(breaches),
+            None => self.get_account_breaches(email).await,
+        }
     }
 
     /// Returns paste dumps where the given email appears.
+    /// An unknown email follows the client's [`NotFoundPolicy`].
     pub async fn get_account_pastes(&self, email: &str) -> HibpResult<Vec<Paste>> {
         let url = format!(
             "https://haveibeenpwned.com/api/v3/pasteaccount/{}",
-            urlencoding::encode(&email)
+            urlencoding::encode(email)
         );
-        Ok(self.request(&url).await?)
+        self.apply_not_found_policy(self.request(&url).await)
+    }
+
+    /// Returns the email's pastes dated after `since` (e.g. the last check), newest first.
+    /// Undated pastes are left out, and an email that isn't in any paste (`404`) returns an empty
+    /// list.
+    pub async fn pastes_since(&self, email: &str, since: DateTime<Utc>) -> HibpResult<Vec<Paste>> {
+        let mut pastes = match self.get_account_pastes(email).await {
+            Ok(pastes) => pastes,
+            Err(HibpError::NotFound) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        pastes.retain(|p| p.date.is_some_and(|date| date > since));
+        pastes.sort_by_key(|p| std::cmp::Reverse(p.date));
+        Ok(pastes)
+    }
+
+    /// Returns true if the email appears in any malware or stealer log breach.
+    /// An email that isn't in any breach (`404`) returns false.
+    pub async fn is_in_malware_breach(&self, email: &str) -> HibpResult<bool> {
+        match self.get_account_breaches(email).await {
+            Ok(breaches) => Ok(breaches.iter().any(|b| b.is_malware || b.is_stealer_log)),
+            Err(HibpError::NotFound) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the email's breaches as `(breach_date, name)` pairs, oldest first (ties sorted by
+    /// name). An email that isn't in any breach (`404`) returns an empty timeline.
+    pub async fn account_breach_timeline(
+        &self,
+        email: &str,
+    ) -> HibpResult<Vec<(NaiveDate, String)>> {
+        let breaches = match self.get_account_breaches(email).await {
+            Ok(breaches) => breaches,
+            Err(HibpError::NotFound) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut timeline: Vec<(NaiveDate, String)> = breaches
+            .into_iter()
+            .map(|b| (b.breach_date, b.name))
+            .collect();
+        timeline.sort();
+        Ok(timeline)
+    }
+
+    /// Returns the email's breaches whose name isn't in `known` (compared case-sensitively), e.g.
+    /// to report only breaches that haven't been reviewed yet. An email that isn't in any breach
+    /// (`404`) returns an empty list.
+    pub async fn check_account_new_breaches(
+        &self,
+        email: &str,
+        known: &std::collections::HashSet<String>,
+    ) -> HibpResult<Vec<Breach>> {
+        let mut breaches = match self.get_account_breaches(email).await {
+            Ok(breaches) => breaches,
+            Err(HibpError::NotFound) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        breaches.retain(|b| !known.contains(&b.name));
+        Ok(breaches)
+    }
+
+    /// Looks up an email's breaches and pastes concurrently, keeping each outcome separate (see
+    /// [`AccountLookup`]). Each lookup follows the client's [`NotFoundPolicy`] on its own.
+    pub async fn lookup_account(&self, email: &str) -> AccountLookup {
+        let (breaches, pastes) = futures_util::future::join(
+            self.get_account_breaches(email),
+            self.get_account_pastes(email),
+        )
+        .await;
+        AccountLookup { breaches, pastes }
+    }
+
+    /// Looks up an email's breaches and checks a password concurrently, keeping each outcome
+    /// separate (see [`IdentityCheck`]). The account lookup follows the client's
+    /// [`NotFoundPolicy`].
+    pub async fn check_identity(&self, email: &str, password: &str) -> IdentityCheck {
+        let (breaches, password_count) = futures_util::future::join(
+            self.get_account_breaches(email),
+            self.check_password(password),
+        )
+        .await;
+        IdentityCheck {
+            breaches,
+            password_count,
+        }
+    }
+
+    /// Looks up an email's breaches and pastes and returns them without identifying details (see
+    /// [`AnonymizedReport`] for exactly what's kept). An email that isn't in any breach or paste
+    /// (`404`) gets empty lists.
+    pub async fn anonymized_account_report(&self, email: &str) -> HibpResult<AnonymizedReport> {
+        let AccountLookup { breaches, pastes } = self.lookup_account(email).await;
+        fn or_empty<T>(result: HibpResult<Vec<T>>) -> HibpResult<Vec<T>> {
+            match result {
+                Err(HibpError::NotFound) => Ok(Vec::new()),
+                result => result,
+            }
+        }
+
+        Ok(AnonymizedReport {
+            email_fingerprint: crate::shared::email_fingerprint(email),
+            breaches: or_empty(breaches)?
+                .into_iter()
+                .map(|b| AnonymizedBreach {
+                    name: b.name,
+                    breach_date: b.breach_date,
+                    data_classes: b.data_classes,
+                })
+                .collect(),
+            pastes: or_empty(pastes)?
+                .into_iter()
+                .map(|p| AnonymizedPaste {
+                    source: p.source,
+                    date: p.date,
+                    email_count: p.email_count,
+                })
+                .collect(),
+        })
+    }
+
+    /// Looks up an email's breach names and returns them as an [`ExposureReceipt`], which can
+    /// be shared without revealing the email or the breaches. An email that isn't in any breach
+    /// (`404`) gets an empty receipt.
+    pub async fn exposure_receipt(&self, email: &str) -> HibpResult<ExposureReceipt> {
+        use sha2::{Digest, Sha256};
+
+        let names = match self.get_account_breach_names(email).await {
+            Ok(names) => names,
+            Err(HibpError::NotFound) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let email_fingerprint = crate::shared::email_fingerprint(email);
+        let mut breach_hashes: Vec<String> = names
+            .iter()
+            .map(|name| hex::encode(Sha256::digest(format!("{email_fingerprint}:{name}"))))
+            .collect();
+        breach_hashes.sort();
+
+        Ok(ExposureReceipt {
+            email_fingerprint,
+            breach_count: breach_hashes.len(),
+            breach_hashes,
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// Looks up the breaches of several emails concurrently and combines them: the distinct
+    /// breaches and data classes across all of them, and the number of breaches per email.
+    /// An email that isn't in any breach (`404`) counts as no breaches.
+    ///
+    /// Requests go through the rate limiter like any other (see [`HibpClient::change_rate_limiter`]).
+    /// Fails with the first error other than [`HibpError::NotFound`].
+    pub async fn combined_account_exposure(
+        &self,
+        emails: &[String],
+    ) -> HibpResult<CombinedExposure> {
+        let lookups = emails.iter().map(|email| async move {
+            match self.get_account_breaches(email).await {
+                Ok(breaches) => Ok(breaches),
+                Err(HibpError::NotFound) => Ok(Vec::new()),
+                Err(e) => Err(e),
+            }
+        });
+        let results = futures_util::future::join_all(lookups).await;
+
+        let mut breaches: std::collections::BTreeMap<String, Breach> = Default::default();
+        let mut per_email = Vec::with_capacity(emails.len());
+        for (email, result) in emails.iter().zip(results) {
+            let found = result?;
+            per_email.push((email.clone(), found.len()));
+            for breach in found {
+                breaches.entry(breach.name.clone()).or_insert(breach);
+            }
+        }
+
+        let data_classes: std::collections::BTreeSet<String> = breaches
+            .values()
+            .flat_map(|b| b.data_classes.iter().cloned())
+            .collect();
+        Ok(CombinedExposure {
+            breaches: breaches.into_values().collect(),
+            data_classes: data_classes.into_iter().collect(),
+            per_email,
+        })
+    }
+
+    /// Returns the email's breach with the most pwned accounts, the most recent one on ties.
+    /// An email that isn't in any breach (`404`) returns `None`.
+    pub async fn largest_breach_for_account(&self, email: &str) -> HibpResult<Option<Breach>> {
+        let breaches = match self.get_account_breaches(email).await {
+            Ok(breaches) => breaches,
+            Err(HibpError::NotFound) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        Ok(breaches
+            .into_iter()
+            .max_by_key(|b| (b.pwn_count, b.breach_date)))
     }
 
     // ╔══════════════════════════════════════════════════════════════════════════════════════════════╗
@@ -351,33 +1642,548 @@ impl HibpClient {
     // ╚══════════════════════════════════════════════════════════════════════════════════════════════╝
 
     /// Gets detailed information about a specific breach by name. Doesn't need an api key
+    /// Breaches are cached per name for the client's lifetime.
     pub async fn get_breach(&self, name: &str) -> HibpResult<Breach> {
+        if let Some(breach) = self.breach_details_cache.lock().unwrap().get(name) {
+            return Ok(breach.clone());
+        }
+
         let url = format!(
             "https://haveibeenpwned.com/api/v3/breach/{}",
-            urlencoding::encode(&name)
+            urlencoding::encode(name)
         );
-        Ok(self.request(&url).await?)
+        let breach: Breach = self.request(&url).await?;
+        self.breach_details_cache
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), breach.clone());
+        Ok(breach)
+    }
+
+    /// Returns true if HIBP has a breach with this exact (case-sensitive) name, e.g. to validate a
+    /// name as it's typed. The client's breach list cache is checked if it was already fetched
+    /// (see [`HibpClient::get_all_breaches`]); otherwise the breach is requested with
+    /// [`HibpClient::get_breach`].
+    pub async fn breach_exists(&self, name: &str) -> HibpResult<bool> {
+        if let Some(breaches) = &self.breach_cache.lock().unwrap().breaches {
+            return Ok(breaches.iter().any(|b| b.name == name));
+        }
+
+        // HIBP matches the name case-insensitively, so compare it with the one returned
+        match self.get_breach(name).await {
+            Ok(breach) => Ok(breach.name == name),
+            Err(HibpError::NotFound) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Gets a breach like [`HibpClient::get_breach`], along with whether `email` appears in it.
+    /// Both lookups run concurrently; the account one only fetches breach names. An email that
+    /// isn't in any breach (`404`) is reported as not affected.
+    pub async fn breach_with_account_status(
+        &self,
+        breach_name: &str,
+        email: &str,
+    ) -> HibpResult<(Breach, bool)> {
+        let (breach, names) = futures_util::future::join(
+            self.get_breach(breach_name),
+            self.get_account_breach_names(email),
+        )
+        .await;
+
+        let breach = breach?;
+        let affected = match names {
+            Ok(names) => names.iter().any(|n| n.eq_ignore_ascii_case(&breach.name)),
+            Err(HibpError::NotFound) => false,
+            Err(e) => return Err(e),
+        };
+        Ok((breach, affected))
+    }
+
+    /// Gets several breaches by name, in input order. Names HIBP doesn't know are skipped.
+    ///
+    /// Each name is looked up in the client's breach list cache first (only if it was already
+    /// fetched, see [`HibpClient::get_all_breaches`]), then requested with
+    /// [`HibpClient::get_breach`], which goes through the rate limiter and caches the result.
+    pub async fn get_breaches_by_names(&self, names: &[String]) -> HibpResult<Vec<Breach>> {
+        let listed: Vec<Option<Breach>> = {
+            let cache = self.breach_cache.lock().unwrap();
+            names
+                .iter()
+                .map(|name| {
+                    cache
+                        .breaches
+                        .as_ref()?
+                        .iter()
+                        .find(|b| &b.name == name)
+                        .cloned()
+                })
+                .collect()
+        };
+
+        let mut breaches = Vec::with_capacity(names.len());
+        for (name, listed) in names.iter().zip(listed) {
+            match listed {
+                Some(breach) => breaches.push(breach),
+                None => match self.get_breach(name).await {
+                    Ok(breach) => breaches.push(breach),
+                    Err(HibpError::NotFound) => {}
+                    Err(e) => return Err(e),
+                },
+            }
+        }
+        Ok(breaches)
     }
 
     /// Returns the most recently added breach (by AddedDate).
     pub async fn get_latest_breach(&self) -> HibpResult<Breach> {
         let url = "https://haveibeenpwned.com/api/v3/latestbreach";
-        Ok(self.request(url).await?)
+        self.request(url).await
+    }
+
+    /// Returns the `n` most recently added breaches from the cached breach list (see
+    /// [`HibpClient::get_all_breaches_cached`]), newest first, or all of them if there are fewer.
+    ///
+    /// Breaches are ordered by `added_date`, then `breach_date`, then name, so ties always come
+    /// out in the same order. Breaches without an `added_date` come last.
+    pub async fn get_latest_breaches(&self, n: usize) -> HibpResult<Vec<Breach>> {
+        let mut breaches = self.get_all_breaches_cached().await?;
+        breaches.sort_by(|a, b| {
+            b.added_date
+                .cmp(&a.added_date)
+                .then_with(|| b.breach_date.cmp(&a.breach_date))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        breaches.truncate(n);
+        Ok(breaches)
     }
 
     /// Return all breaches for a domain.
-    pub async fn get_domain_breaches(&self, domain: &str) -> HibpResult<Vec<Breach>> {
+    ///
+    /// With [`DomainMatch::IncludeSubdomains`] the whole breach list is downloaded (several MB)
+    /// and filtered locally, case-insensitively.
+    pub async fn get_domain_breaches(
+        &self,
+        domain: &str,
+        matching: DomainMatch,
+    ) -> HibpResult<Vec<Breach>> {
+        match matching {
+            DomainMatch::Exact => {
+                let url = format!(
+                    "https://haveibeenpwned.com/api/v3/breaches?domain={}",
+                    urlencoding::encode(domain)
+                );
+                self.request(&url).await
+            }
+            DomainMatch::IncludeSubdomains => {
+                let domain = domain.trim().trim_start_matches('.').to_ascii_lowercase();
+                let subdomain_suffix = format!(".{domain}");
+                let mut breaches = self.get_all_breaches().await?;
+                breaches.retain(|b| {
+                    let breach_domain = b.domain.to_ascii_lowercase();
+                    breach_domain == domain || breach_domain.ends_with(&subdomain_suffix)
+                });
+                Ok(breaches)
+            }
+        }
+    }
+
+    /// Searches a domain verified under the API key, returning each breached alias (the part of
+    /// the email before `@`) with the names of the breaches it appears in.
+    ///
+    /// With `verified_only`, aliases whose breaches are all unverified are dropped. This needs the
+    /// full breach list to know which breaches are unverified, so it may download it (several MB)
+    /// if it isn't cached yet (see [`HibpClient::get_all_breaches_cached`]).
+    pub async fn search_domain(
+        &self,
+        domain: &str,
+        verified_only: bool,
+    ) -> HibpResult<HashMap<String, Vec<String>>> {
         let url = format!(
-            "https://haveibeenpwned.com/api/v3/breaches?domain={}",
+            "https://haveibeenpwned.com/api/v3/breacheddomain/{}",
             urlencoding::encode(domain)
         );
-        Ok(self.request(&url).await?)
+        let mut aliases: HashMap<String, Vec<String>> = self.request(&url).await?;
+
+        if verified_only {
+            let unverified: std::collections::HashSet<String> = self
+                .get_all_breaches_cached()
+                .await?
+                .into_iter()
+                .filter(|b| !b.is_verified)
+                .map(|b| b.name)
+                .collect();
+            aliases.retain(|_, breaches| breaches.iter().any(|name| !unverified.contains(name)));
+        }
+
+        Ok(aliases)
+    }
+
+    /// Returns the domain's most recent breach (by `breach_date`, then `added_date`),
+    /// or `None` if the domain has no breaches.
+    pub async fn latest_breach_for_domain(&self, domain: &str) -> HibpResult<Option<Breach>> {
+        let breaches = self.get_domain_breaches(domain, DomainMatch::Exact).await?;
+        Ok(breaches
+            .into_iter()
+            .max_by_key(|b| (b.breach_date, b.added_date)))
+    }
+
+    /// Streams all breaches, yielding each one as soon as it is parsed from the response body so
+    /// they can be displayed before the whole list (several MB) has downloaded.
+    /// The stream ends after the first error. [`HibpClient::get_all_breaches`] collects them instead.
+    pub fn stream_all_breaches(&self) -> impl Stream<Item = HibpResult<Breach>> + '_ {
+        let url = "https://haveibeenpwned.com/api/v3/breaches";
+        futures_util::stream::try_unfold(None, move |state: Option<BreachStream>| async move {
+            let mut state = match state {
+                Some(state) => state,
+                None => {
+                    let req = self
+                        .get_with_key(url, &self.current_api_key())
+                        .timeout(self.config.breach_list_timeout());
+                    let response = self.send_limited(req).await?;
+                    BreachStream::new(Self::handle_response(response).await?)
+                }
+            };
+            Ok(state
+                .next_breach()
+                .await?
+                .map(|breach| (breach, Some(state))))
+        })
+    }
+
+    /// Downloads a breach logo (`Breach::logo_path`) and returns its bytes with the `Content-Type`
+    /// header, so the image can be decoded correctly. Logos are cached per URL for the client's
+    /// lifetime.
+    ///
+    /// Some logos are SVGs (`image/svg+xml`) and need an SVG loader rather than a raster decoder.
+    ///
+    /// # Errors
+    /// - Returns [`HibpError::UnexpectedContentType`] if the response isn't an image
+    pub async fn fetch_logo_with_type(
+        &self,
+        logo_path: &str,
+    ) -> HibpResult<(Vec<u8>, Option<String>)> {
+        if let Some(logo) = self.logo_cache.lock().unwrap().get(logo_path) {
+            return Ok(logo.clone());
+        }
+
+        let resp = self
+            .client
+            .get(logo_path)
+            .header("User-Agent", &self.user_agent)
+            .send()
+            .await?;
+        let resp = Self::handle_response(resp).await?;
+
+        let content_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|ct| ct.to_str().ok())
+            .map(str::to_string);
+        if let Some(ct) = &content_type {
+            if !ct.trim().to_ascii_lowercase().starts_with("image/") {
+                return Err(HibpError::UnexpectedContentType(ct.clone()));
+            }
+        }
+
+        let logo = (resp.bytes().await?.to_vec(), content_type);
+        self.logo_cache
+            .lock()
+            .unwrap()
+            .insert(logo_path.to_string(), logo.clone());
+        Ok(logo)
+    }
+
+    /// Renders an email's breaches and pastes as a self-contained HTML report, breaches first, with
+    /// each breach's logo embedded as a `data:` URI so the file can be shared on its own.
+    ///
+    /// Logos come from [`HibpClient::fetch_logo_with_type`], so each one is downloaded at most once.
+    /// A breach whose logo is missing or fails to download is rendered without one.
+    pub async fn account_report_to_html(&self, breaches: &[Breach], pastes: &[Paste]) -> String {
+        use base64::Engine;
+        use std::fmt::Write;
+
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Breach report</title>\n</head>\n<body>\n",
+        );
+
+        let _ = writeln!(html, "<h1>Breaches ({})</h1>", breaches.len());
+        for b in breaches {
+            let logo = match &b.logo_path {
+                Some(path) => self.fetch_logo_with_type(path).await.ok(),
+                None => None,
+            };
+
+            html.push_str("<section>\n");
+            if let Some((bytes, content_type)) = logo {
+                let content_type = content_type.unwrap_or_else(|| "image/png".to_string());
+                let _ = writeln!(
+                    html,
+                    "<img src=\"data:{};base64,{}\" alt=\"\" height=\"48\">",
+                    escape_html(&content_type),
+                    base64::engine::general_purpose::STANDARD.encode(bytes)
+                );
+            }
+            let _ = writeln!(
+                html,
+                "<h2>{}</h2>\n<p>{} &middot; {} &middot; {} accounts</p>\n<p>{}</p>",
+                escape_html(&b.title),
+                escape_html(&b.domain),
+                b.breach_date,
+                crate::shared::format_count(b.pwn_count),
+                // Tags are stripped; the entities left are already valid HTML
+                crate::shared::html_to_text(&b.description)
+            );
+            if !b.data_classes.is_empty() {
+                let _ = writeln!(
+                    html,
+                    "<p>Compromised data: {}</p>",
+                    escape_html(&b.data_classes.join(", "))
+                );
+            }
+            html.push_str("</section>\n");
+        }
+
+        let _ = writeln!(html, "<h1>Pastes ({})</h1>", pastes.len());
+        if !pastes.is_empty() {
+            html.push_str(
+                "<table>\n<tr><th>Title</th><th>Date</th><th>Source</th><th>Emails</th></tr>\n",
+            );
+            for p in pastes {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape_html(p.title.as_deref().unwrap_or("")),
+                    p.date
+                        .map(|d| d.date_naive().to_string())
+                        .unwrap_or_default(),
+                    escape_html(p.source.as_deref().unwrap_or("")),
+                    p.email_count
+                        .map(crate::shared::format_count)
+                        .unwrap_or_default()
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
     }
 
-    /// Returns all breaches.
+    /// Returns all breaches, and keeps them in the client's breach cache.
+    /// When the cached list is still current, HIBP answers `304` and the cached list is returned.
     pub async fn get_all_breaches(&self) -> HibpResult<Vec<Breach>> {
-        let url = "https://haveibeenpwned.com/api/v3/breaches".to_string();
-        Ok(self.request(&url).await?)
+        self.breach_sync().run().await
+    }
+
+    /// Returns all breaches from the client's breach cache, fetching them only if the cache is
+    /// empty. The list may be as old as the last [`HibpClient::get_all_breaches`] call.
+    pub async fn get_all_breaches_cached(&self) -> HibpResult<Vec<Breach>> {
+        if let Some(breaches) = &self.breach_cache.lock().unwrap().breaches {
+            return Ok(breaches.clone());
+        }
+        self.get_all_breaches().await
+    }
+
+    /// Returns the total number of breaches and of pwned accounts, computed from the cached
+    /// breach list (see [`HibpClient::get_all_breaches_cached`]).
+    pub async fn global_stats(&self) -> HibpResult<GlobalStats> {
+        let breaches = self.get_all_breaches_cached().await?;
+        Ok(GlobalStats::from_breaches(&breaches))
+    }
+
+    /// Fetches the latest breach, the subscription status and the global stats concurrently, for
+    /// a dashboard showing them together. Requests still go through the rate limiter, and each
+    /// outcome is kept separate (see [`DashboardSnapshot`]).
+    pub async fn dashboard_snapshot(&self) -> DashboardSnapshot {
+        let (latest_breach, subscription, stats) = futures_util::future::join3(
+            self.get_latest_breach(),
+            self.get_subscription(),
+            self.global_stats(),
+        )
+        .await;
+        DashboardSnapshot {
+            latest_breach,
+            subscription,
+            stats,
+        }
+    }
+
+    /// Returns every distinct non-empty breach domain in the cached breach list (see
+    /// [`HibpClient::get_all_breaches_cached`]), sorted, e.g. to suggest domains as one is typed.
+    pub async fn all_breach_domains(&self) -> HibpResult<Vec<String>> {
+        let domains: std::collections::BTreeSet<String> = self
+            .get_all_breaches_cached()
+            .await?
+            .into_iter()
+            .map(|b| b.domain.trim().to_string())
+            .filter(|domain| !domain.is_empty())
+            .collect();
+        Ok(domains.into_iter().collect())
+    }
+
+    /// Returns the breaches of an email provider's own service, e.g. a breach of `yahoo.com`
+    /// itself for `provider_domain = "yahoo.com"`, newest first. Subdomains of the provider
+    /// count, matching is case-insensitive, and the cached breach list is used (see
+    /// [`HibpClient::get_all_breaches_cached`]).
+    ///
+    /// This is not about breached accounts whose address is at the provider: breaches of other
+    /// sites leak plenty of `@yahoo.com` addresses, but HIBP only reveals those per account (see
+    /// [`HibpClient::get_account_breaches`]) or through [`HibpClient::search_domain`], which needs
+    /// the domain to be verified under the API key and so isn't available for public providers.
+    pub async fn breaches_of_email_provider(
+        &self,
+        provider_domain: &str,
+    ) -> HibpResult<Vec<Breach>> {
+        let domain = provider_domain
+            .trim()
+            .trim_start_matches('.')
+            .to_ascii_lowercase();
+        if domain.is_empty() {
+            return Ok(Vec::new());
+        }
+        let subdomain_suffix = format!(".{domain}");
+        let mut breaches: Vec<Breach> = self
+            .get_all_breaches_cached()
+            .await?
+            .into_iter()
+            .filter(|b| {
+                let breach_domain = b.domain.trim().to_ascii_lowercase();
+                breach_domain == domain || breach_domain.ends_with(&subdomain_suffix)
+            })
+            .collect();
+        breaches.sort_by_key(|b| std::cmp::Reverse(b.breach_date));
+        Ok(breaches)
+    }
+
+    /// Returns the cached breach list grouped by domain, each group sorted by breach date (oldest
+    /// first). Breaches without a domain are grouped under `"(no domain)"`.
+    pub async fn breaches_grouped_by_domain(
+        &self,
+    ) -> HibpResult<std::collections::BTreeMap<String, Vec<Breach>>> {
+        let mut by_domain: std::collections::BTreeMap<String, Vec<Breach>> = Default::default();
+        for breach in self.get_all_breaches_cached().await? {
+            let domain = match breach.domain.trim() {
+                "" => "(no domain)".to_string(),
+                domain => domain.to_string(),
+            };
+            by_domain.entry(domain).or_default().push(breach);
+        }
+
+        for breaches in by_domain.values_mut() {
+            breaches.sort_by_key(|b| b.breach_date);
+        }
+        Ok(by_domain)
+    }
+
+    /// Returns the subscription-free breaches from the cached breach list, sorted by name.
+    ///
+    /// HIBP lets these breaches be matched against an account without an API key (see
+    /// [`Breach::is_subscription_free`]); every other breach needs a subscription to search.
+    pub async fn get_subscription_free_breaches(&self) -> HibpResult<Vec<Breach>> {
+        let mut breaches: Vec<Breach> = self
+            .get_all_breaches_cached()
+            .await?
+            .into_iter()
+            .filter(|b| b.is_subscription_free)
+            .collect();
+        breaches.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(breaches)
+    }
+
+    /// Returns the breaches from the cached breach list (see [`HibpClient::get_all_breaches_cached`])
+    /// that expose `data_class` (matched case-insensitively), most pwned accounts first. A data
+    /// class no breach exposes, including an unknown one, returns an empty list.
+    pub async fn breaches_exposing(&self, data_class: &str) -> HibpResult<Vec<Breach>> {
+        let data_class = data_class.trim();
+        let mut breaches: Vec<Breach> = self
+            .get_all_breaches_cached()
+            .await?
+            .into_iter()
+            .filter(|b| {
+                b.data_classes
+                    .iter()
+                    .any(|class| class.eq_ignore_ascii_case(data_class))
+            })
+            .collect();
+        breaches.sort_by_key(|b| std::cmp::Reverse(b.pwn_count));
+        Ok(breaches)
+    }
+
+    /// Returns the breaches whose record was modified after `since` (e.g. the last sync), from a
+    /// freshly fetched breach list (see [`HibpClient::get_all_breaches`]). Useful to update only
+    /// the cached records that changed, such as a revised `pwn_count`.
+    /// Breaches without a `modified_date` are left out.
+    pub async fn breaches_modified_since(&self, since: DateTime<Utc>) -> HibpResult<Vec<Breach>> {
+        Ok(self
+            .get_all_breaches()
+            .await?
+            .into_iter()
+            .filter(|b| b.modified_date.is_some_and(|modified| modified > since))
+            .collect())
+    }
+
+    /// Returns the breaches from the cached breach list (see
+    /// [`HibpClient::get_all_breaches_cached`]) whose `breach_date` is within `from..=to`, oldest
+    /// first. A `None` bound leaves that side open; `verified_only` drops unverified breaches.
+    pub async fn breaches_in_range(
+        &self,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        verified_only: bool,
+    ) -> HibpResult<Vec<Breach>> {
+        let mut breaches: Vec<Breach> = self
+            .get_all_breaches_cached()
+            .await?
+            .into_iter()
+            .filter(|b| from.is_none_or(|from| b.breach_date >= from))
+            .filter(|b| to.is_none_or(|to| b.breach_date <= to))
+            .filter(|b| b.is_verified || !verified_only)
+            .collect();
+        breaches.sort_by(|a, b| {
+            a.breach_date
+                .cmp(&b.breach_date)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Ok(breaches)
+    }
+
+    /// Returns the list of all data classes breaches can expose (e.g. "Email addresses").
+    pub async fn get_data_classes(&self) -> HibpResult<Vec<String>> {
+        let url = "https://haveibeenpwned.com/api/v3/dataclasses";
+        self.request(url).await
+    }
+
+    /// Returns the data classes list, stored in `cache_dir` and fetched again only once the copy
+    /// there is older than `ttl`.
+    ///
+    /// If fetching fails, the stored copy is returned regardless of its age when there is one.
+    /// Storing is best effort: a cache that can't be written only means fetching next time.
+    pub async fn get_data_classes_cached(
+        &self,
+        cache_dir: &std::path::Path,
+        ttl: std::time::Duration,
+    ) -> HibpResult<Vec<String>> {
+        let path = cache_dir.join("dataclasses.json");
+
+        let cached: Option<Vec<String>> = match read_json_cache(&path, ttl).await {
+            Some((classes, true)) => return Ok(classes),
+            Some((classes, false)) => Some(classes),
+            None => None,
+        };
+
+        match self.get_data_classes().await {
+            Ok(classes) => {
+                if let Ok(json) = serde_json::to_vec(&classes) {
+                    if tokio::fs::create_dir_all(cache_dir).await.is_ok() {
+                        let _ = crate::shared::write_atomic_async(&path, json).await;
+                    }
+                }
+                Ok(classes)
+            }
+            Err(e) => cached.ok_or(e),
+        }
     }
 
     // ╔══════════════════════════════════════════════════════════════════════════════════════════════╗
@@ -392,7 +2198,9 @@ impl HibpClient {
     /// - Sends only the first 5 chars (prefix) to the HIBP k-Anonymity API.
     /// - Looks for the remaining suffix in the returned dataset.
     ///
-    /// Returns the number of breaches in which the password appeared.
+    /// Returns the number of breaches in which the password appeared, 0 if it isn't listed
+    /// (including a match on a padding entry). Use [`HibpClient::lookup_password`] to tell an
+    /// unlisted password apart from a listed one with a count of 0.
     ///
     /// ### Security
     /// The password is never directly sent to HIBP,
@@ -400,33 +2208,1891 @@ impl HibpClient {
     pub async fn check_password(&self, password: impl AsRef<[u8]>) -> HibpResult<u64> {
         // Convert password into uppercase SHA1 hash
         let sha1_hex = hex::encode_upper(Sha1::digest(password.as_ref()));
-        let (prefix, suffix) = sha1_hex.split_at(5);
+        self.check_password_hash(&sha1_hex).await
+    }
 
-        // Query the Pwned Passwords k-Anonymity API and parse the response
-        let resp = self
-            .client
-            .get(format!("https://api.pwnedpasswords.com/range/{prefix}"))
-            .header("User-Agent", &self.user_agent)
-            .header("Add-Padding", "true")
-            .send()
+    /// Looks a password up like [`HibpClient::check_password`], but returns `None` when it isn't
+    /// listed instead of 0.
+    ///
+    /// A match on a padding entry (count 0 in a padded response) is `None`; a 0-count match in an
+    /// unpadded response is `Some(0)`, "found, zero occurrences" (see
+    /// [`HibpClient::change_padding`]).
+    pub async fn lookup_password(&self, password: impl AsRef<[u8]>) -> HibpResult<Option<u64>> {
+        let sha1_hex = hex::encode_upper(Sha1::digest(password.as_ref()));
+        self.lookup_password_hash(&sha1_hex, &RangeCell::new())
+            .await
+    }
+
+    /// Checks how many times a password's NTLM hash has appeared in breaches, like
+    /// [`HibpClient::check_password`] does for its SHA1 hash.
+    ///
+    /// NTLM is how Windows and Active Directory store passwords, so this count is only relevant
+    /// when auditing those (e.g. hashes dumped from a domain controller). Elsewhere, use
+    /// [`HibpClient::check_password`]. The local common password list isn't consulted.
+    pub async fn check_password_ntlm(&self, password: &str) -> HibpResult<u64> {
+        // NTLM is MD4 over the UTF-16LE encoded password
+        let utf16: Vec<u8> = password.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let ntlm_hex = hex::encode_upper(md4::Md4::digest(&utf16));
+        let (prefix, suffix) = ntlm_hex.split_at(5);
+
+        // Unlisted and padding matches are both 0, as in `check_password`
+        let body = self.fetch_ntlm_range(prefix).await?;
+        Ok(Self::match_suffix(&body, suffix, self.add_padding).unwrap_or(0))
+    }
+
+    /// Checks a password's SHA1 and NTLM hashes concurrently (see [`PasswordModes`]). The NTLM
+    /// count only matters for Windows and Active Directory audits.
+    pub async fn check_password_all_modes(&self, password: &str) -> HibpResult<PasswordModes> {
+        let (sha1_count, ntlm_count) = futures_util::future::join(
+            self.check_password(password),
+            self.check_password_ntlm(password),
+        )
+        .await;
+        Ok(PasswordModes {
+            sha1_count: sha1_count?,
+            ntlm_count: ntlm_count?,
+        })
+    }
+
+    /// Same as [`HibpClient::check_password`], but the plaintext is wiped as soon as it has been
+    /// hashed, before the range is requested.
+    ///
+    /// Only this buffer is wiped: copies made elsewhere (e.g. by a text input) aren't affected.
+    #[cfg(feature = "zeroize")]
+    pub async fn check_password_zeroizing(
+        &self,
+        password: zeroize::Zeroizing<String>,
+    ) -> HibpResult<u64> {
+        let sha1_hex = hex::encode_upper(Sha1::digest(password.as_bytes()));
+        drop(password);
+        self.check_password_hash(&sha1_hex).await
+    }
+
+    /// Looks up a password by its SHA1 hex, in either case (see [`HibpClient::check_password`]),
+    /// for callers that shouldn't keep the plaintext around.
+    ///
+    /// # Errors
+    /// - Returns [`HibpError::InvalidHash`] if `sha1_hex` isn't 40 hex chars
+    pub async fn check_password_hash(&self, sha1_hex: &str) -> HibpResult<u64> {
+        if sha1_hex.len() != 40 || !sha1_hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(HibpError::InvalidHash(sha1_hex.to_string()));
+        }
+        let sha1_hex = sha1_hex.to_ascii_uppercase();
+        let count = self
+            .lookup_password_hash(&sha1_hex, &RangeCell::new())
             .await?;
+        Ok(count.unwrap_or(0))
+    }
+
+    /// [`HibpClient::lookup_password`] for a valid uppercase SHA1 hex. The range body is taken
+    /// from `range` if it was already fetched and stored there otherwise, so hashes with the same
+    /// prefix share one request.
+    async fn lookup_password_hash(
+        &self,
+        sha1_hex: &str,
+        range: &RangeCell,
+    ) -> HibpResult<Option<u64>> {
+        // Common passwords are answered locally
+        if let Some(&count) = self
+            .common_passwords
+            .as_ref()
+            .and_then(|list| list.get(sha1_hex))
+        {
+            if count > 0 {
+                return Ok(Some(count));
+            }
+        }
+
+        let (prefix, suffix) = sha1_hex.split_at(5);
 
-        let body = self.handle_response(resp).await?.text().await?;
+        // Query the Pwned Passwords k-Anonymity API
+        let body = range.get_or_try_init(|| self.fetch_range(prefix)).await?;
 
         // Check if the suffix exists in returned hash list
-        let target_suffix = suffix.to_ascii_uppercase();
-        let count = body
-            .lines()
-            .find_map(|line| {
-                let (sfx, cnt) = line.split_once(':')?;
-                if sfx.eq_ignore_ascii_case(&target_suffix) {
-                    cnt.trim().parse::<u64>().ok()
-                } else {
-                    None
-                }
+        Ok(Self::match_suffix(body, suffix, self.add_padding))
+    }
+
+    /// Checks two passwords like [`HibpClient::check_password`] (common password list included),
+    /// returning both counts. When their hashes share a prefix, the range is requested once for
+    /// both.
+    pub async fn check_password_pair(
+        &self,
+        a: impl AsRef<[u8]>,
+        b: impl AsRef<[u8]>,
+    ) -> HibpResult<(u64, u64)> {
+        let hash_a = hex::encode_upper(Sha1::digest(a.as_ref()));
+        let hash_b = hex::encode_upper(Sha1::digest(b.as_ref()));
+
+        let (range_a, own_range_b) = (RangeCell::new(), RangeCell::new());
+        let range_b = if hash_a[..5] == hash_b[..5] {
+            &range_a
+        } else {
+            &own_range_b
+        };
+        let (count_a, count_b) = futures_util::future::join(
+            self.lookup_password_hash(&hash_a, &range_a),
+            self.lookup_password_hash(&hash_b, range_b),
+        )
+        .await;
+        Ok((count_a?.unwrap_or(0), count_b?.unwrap_or(0)))
+    }
+
+    /// Compares how exposed two passwords are (e.g. an old and a new one): `Greater` if `a`
+    /// appeared in breaches more often than `b`, `Less` if less often, `Equal` otherwise.
+    ///
+    /// Only the comparison is returned, so callers that show it don't get to handle (or leak)
+    /// the counts themselves; use [`HibpClient::check_password_pair`] for those. Requests are
+    /// shared as in [`HibpClient::check_password_pair`].
+    pub async fn compare_password_exposure(
+        &self,
+        a: impl AsRef<[u8]>,
+        b: impl AsRef<[u8]>,
+    ) -> HibpResult<std::cmp::Ordering> {
+        let (count_a, count_b) = self.check_password_pair(a, b).await?;
+        Ok(count_a.cmp(&count_b))
+    }
+
+    /// Returns true if the password appeared in breaches at most `max_count` times.
+    /// `max_count == 0` is the strict setting: any appearance makes the password unacceptable.
+    pub async fn is_password_acceptable(
+        &self,
+        password: impl AsRef<[u8]>,
+        max_count: u64,
+    ) -> HibpResult<bool> {
+        Ok(self.check_password(password).await? <= max_count)
+    }
+
+    /// Checks several passwords, requesting each distinct SHA1 prefix only once.
+    ///
+    /// Passwords whose hashes share a 5 chars prefix are matched against the same range response,
+    /// which saves requests on large wordlists. Returns the `(password, count)` pairs in input
+    /// order as a [`PasswordGroupReport`].
+    ///
+    /// With `options.max_requests` set, only that many ranges are fetched, in hash prefix order,
+    /// which amounts to a random sample of the passwords. The rest are reported as not checked.
+    ///
+    /// Up to 8 ranges are fetched concurrently; with [`HibpClient::change_prefer_http2`] they share
+    /// one connection instead of opening one each.
+    ///
+    /// Transient failures (rate limiting, outages, timeouts) are retried, taking from a budget of
+    /// `options.retry_budget` retries shared by the whole batch, so a sustained outage ends the
+    /// batch instead of retrying every range. Retries wait as long as the response asked
+    /// (`Retry-After`, capped), or a second if it didn't. Stops at the first range that fails otherwise.
+    ///
+    /// Padding follows `options.add_padding` (see [`PasswordCheckOptions`] before disabling it).
+    ///
+    /// # Errors
+    /// - Returns [`HibpError::RetryBudgetExhausted`] once a range fails with no retries left
+    pub async fn check_password_group(
+        &self,
+        passwords: &[String],
+        options: PasswordCheckOptions,
+    ) -> HibpResult<PasswordGroupReport> {
+        let hashes: Vec<String> = passwords
+            .iter()
+            .map(|p| hex::encode_upper(Sha1::digest(p.as_bytes())))
+            .collect();
+
+        // Group the password indexes by hash prefix
+        let mut by_prefix: std::collections::BTreeMap<&str, Vec<usize>> = Default::default();
+        for (i, hash) in hashes.iter().enumerate() {
+            by_prefix.entry(&hash[..5]).or_default().push(i);
+        }
+
+        // Fetch a few ranges at once (multiplexed over one connection with HTTP/2)
+        let budget = std::sync::atomic::AtomicU32::new(options.retry_budget);
+        let budget = &budget;
+        let max_requests = options.max_requests.unwrap_or(usize::MAX);
+        let mut ranges = futures_util::stream::iter(by_prefix.into_iter().take(max_requests))
+            .map(|(prefix, indexes)| async move {
+                let body = self
+                    .fetch_range_with_budget(prefix, options.add_padding, budget)
+                    .await;
+                (body, indexes)
             })
-            .unwrap_or(0);
+            .buffer_unordered(RANGE_CONCURRENCY);
+
+        let mut counts = vec![None; passwords.len()];
+        while let Some((body, indexes)) = ranges.next().await {
+            let entries: HashMap<String, u64> = parse_range_body(&body?).into_iter().collect();
+            for i in indexes {
+                let count = entries
+                    .get(&hashes[i][5..])
+                    .and_then(|&count| Self::found_count(count, options.add_padding));
+                counts[i] = Some(count.unwrap_or(0));
+            }
+        }
+
+        Ok(PasswordGroupReport {
+            skipped: counts.iter().filter(|c| c.is_none()).count(),
+            counts: passwords.iter().cloned().zip(counts).collect(),
+        })
+    }
+
+    /// [`HibpClient::fetch_range`], retrying transient failures while `budget` has retries left.
+    async fn fetch_range_with_budget(
+        &self,
+        prefix: &str,
+        padded: bool,
+        budget: &std::sync::atomic::AtomicU32,
+    ) -> HibpResult<String> {
+        use std::sync::atomic::Ordering;
+
+        loop {
+            match self.fetch_range_padded(prefix, padded).await {
+                Err(e) if e.is_retryable() => {
+                    let took_retry = budget
+                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                            left.checked_sub(1)
+                        })
+                        .is_ok();
+                    if !took_retry {
+                        return Err(HibpError::RetryBudgetExhausted(Box::new(e)));
+                    }
+                    tokio::time::sleep(e.retry_after().unwrap_or(RETRY_DELAY)).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the raw range response body for a SHA1 hash prefix (5 hex chars), one
+    /// `SUFFIX:COUNT` entry per line.
+    /// Requests for the same prefix are shared if [`HibpClient::change_range_dedup`] is enabled.
+    pub async fn fetch_range(&self, prefix: &str) -> HibpResult<String> {
+        let Some(window) = self.range_dedup_window else {
+            return self.fetch_range_padded(prefix, self.add_padding).await;
+        };
+
+        let cell = {
+            let mut recent = self.recent_ranges.lock().unwrap();
+            recent.retain(|_, (started, _)| started.elapsed() < window);
+            let (_, cell) = recent
+                .entry(prefix.to_ascii_uppercase())
+                .or_insert_with(|| (std::time::Instant::now(), Arc::default()));
+            Arc::clone(cell)
+        };
+        cell.get_or_try_init(|| self.fetch_range_padded(prefix, self.add_padding))
+            .await
+            .cloned()
+    }
+
+    /// Returns the raw range response body for an NTLM hash prefix (5 hex chars), in the same
+    /// format as [`HibpClient::fetch_range`].
+    pub async fn fetch_ntlm_range(&self, prefix: &str) -> HibpResult<String> {
+        let url = format!("https://api.pwnedpasswords.com/range/{prefix}?mode=ntlm");
+        self.fetch_range_url(&url, self.add_padding).await
+    }
+
+    /// [`HibpClient::fetch_range`], asking for a padded response only if `padded` is true.
+    async fn fetch_range_padded(&self, prefix: &str, padded: bool) -> HibpResult<String> {
+        let url = format!("https://api.pwnedpasswords.com/range/{prefix}");
+        self.fetch_range_url(&url, padded).await
+    }
+
+    /// Requests a range response body from `url`, padded if `padded` is true.
+    async fn fetch_range_url(&self, url: &str, padded: bool) -> HibpResult<String> {
+        let mut req = self
+            .password_client()
+            .get(url)
+            .header("User-Agent", &self.user_agent)
+            .timeout(self.config.password_timeout());
+        if padded {
+            req = req.header("Add-Padding", "true");
+        }
+        let resp = req.send().await?;
+
+        Ok(Self::handle_response(resp).await?.text().await?)
+    }
+
+    /// Returns the `ETag` of a password range (5 hex chars prefix) without downloading it, so cached
+    /// ranges can be revalidated cheaply. Returns `None` if the response carries no `ETag`.
+    pub async fn password_range_etag(&self, prefix: &str) -> HibpResult<Option<String>> {
+        let mut req = self
+            .password_client()
+            .head(format!("https://api.pwnedpasswords.com/range/{prefix}"))
+            .header("User-Agent", &self.user_agent)
+            .timeout(self.config.password_timeout());
+        if self.add_padding {
+            req = req.header("Add-Padding", "true");
+        }
+        let resp = Self::handle_response(req.send().await?).await?;
+
+        Ok(resp
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string))
+    }
+
+    /// Looks up a hash suffix in a range response body.
+    ///
+    /// - Returns `Some(count)` when the suffix is listed.
+    /// - Returns `None` when it isn't listed.
+    ///
+    /// When the response was padded (`padded == true`), entries with a count of 0 are the synthetic
+    /// padding entries, so a match on one of them is reported as `None`. Without padding HIBP never
+    /// emits 0-count entries, so such a match is reported as `Some(0)` ("found, zero occurrences").
+    fn match_suffix(body: &str, suffix: &str, padded: bool) -> Option<u64> {
+        parse_range_body(body)
+            .into_iter()
+            .find(|(sfx, _)| sfx.eq_ignore_ascii_case(suffix))
+            .and_then(|(_, count)| Self::found_count(count, padded))
+    }
+
+    /// Interprets the count of a matched range entry (see [`HibpClient::match_suffix`]).
+    fn found_count(count: u64, padded: bool) -> Option<u64> {
+        match count {
+            0 if padded => None,
+            count => Some(count),
+        }
+    }
+}
+
+/// Reads a JSON cache file, returning its contents and whether the file is younger than `ttl`.
+/// Returns `None` if the file is missing or doesn't hold a valid `T`.
+async fn read_json_cache<T: DeserializeOwned>(
+    path: &std::path::Path,
+    ttl: std::time::Duration,
+) -> Option<(T, bool)> {
+    let value = serde_json::from_slice(&tokio::fs::read(path).await.ok()?).ok()?;
+    let fresh = tokio::fs::metadata(path)
+        .await
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < ttl);
+    Some((value, fresh))
+}
+
+/// Returns `interval` shifted by a pseudo-random amount of up to 10% either way.
+fn jittered(interval: std::time::Duration) -> std::time::Duration {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    // Maps the clock's nanoseconds to a factor between 0.9 and 1.1
+    let factor = 0.9 + 0.2 * (nanos as f64 / 1e9);
+    interval.mul_f64(factor)
+}
+
+/// Saves the name of the last breach [`HibpClient::start_latest_breach_monitor`] has seen to
+/// `path`, if it keeps one. A failure is logged.
+async fn save_last_seen(path: Option<&std::path::Path>, name: &str) {
+    if let Some(path) = path {
+        if let Err(e) = crate::shared::write_atomic_async(path, name.as_bytes().to_vec()).await {
+            eprintln!("Failed to save the last seen breach: {e}");
+        }
+    }
+}
+
+/// Handle to the task started by [`HibpClient::start_background_refresh`] or
+/// [`HibpClient::start_latest_breach_monitor`]. Dropping the handle stops the task too.
+pub struct BackgroundRefresh {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl BackgroundRefresh {
+    /// Stops the background task.
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for BackgroundRefresh {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Fetches the full breach list into a shared [`BreachCache`]. Owns everything it needs so it can
+/// run on a background task.
+struct BreachSync {
+    client: Client,
+    user_agent: String,
+    time_out: std::time::Duration,
+    cache: Arc<Mutex<BreachCache>>,
+}
+
+impl BreachSync {
+    /// Re-fetches the breach list, sending the cached `ETag` so an unchanged list is answered with
+    /// `304` instead of a full download. Returns the up to date list.
+    async fn run(&self) -> HibpResult<Vec<Breach>> {
+        let mut req = self
+            .client
+            .get("https://haveibeenpwned.com/api/v3/breaches")
+            .header("User-Agent", &self.user_agent)
+            .timeout(self.time_out);
+
+        let cached_etag = {
+            let cache = self.cache.lock().unwrap();
+            cache.breaches.as_ref().and(cache.etag.clone())
+        };
+        if let Some(etag) = cached_etag {
+            req = req.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+
+        let response = req.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(breaches) = &self.cache.lock().unwrap().breaches {
+                return Ok(breaches.clone());
+            }
+        }
+
+        let response = HibpClient::handle_response(response).await?;
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        let breaches: Vec<Breach> = response.json().await?;
+
+        *self.cache.lock().unwrap() = BreachCache {
+            breaches: Some(breaches.clone()),
+            etag,
+        };
+        Ok(breaches)
+    }
+}
+
+/// Incremental parser over a response body holding a JSON array of breaches.
+struct BreachStream {
+    body: Pin<Box<dyn Stream<Item = reqwest::Result<Vec<u8>>> + Send>>,
+    /// Received bytes that haven't been parsed yet.
+    buf: Vec<u8>,
+    /// Whether the opening `[` has been consumed.
+    opened: bool,
+}
+
+impl BreachStream {
+    fn new(response: Response) -> Self {
+        Self {
+            body: Box::pin(
+                response
+                    .bytes_stream()
+                    .map(|chunk| chunk.map(|b| b.to_vec())),
+            ),
+            buf: Vec::new(),
+            opened: false,
+        }
+    }
+
+    /// Returns the next breach of the array, or `None` once the closing `]` is reached.
+    async fn next_breach(&mut self) -> HibpResult<Option<Breach>> {
+        loop {
+            // Skip whitespace, and the commas between items once inside the array
+            let skip = self
+                .buf
+                .iter()
+                .take_while(|b| b.is_ascii_whitespace() || (self.opened && **b == b','))
+                .count();
+            self.buf.drain(..skip);
+
+            match self.buf.first() {
+                None => {}
+                Some(b'[') if !self.opened => {
+                    self.opened = true;
+                    self.buf.drain(..1);
+                    continue;
+                }
+                Some(_) if !self.opened => {
+                    return Err(HibpError::ParseError(
+                        "expected a JSON array of breaches".to_string(),
+                    ));
+                }
+                Some(b']') => return Ok(None),
+                Some(_) => {
+                    let mut items =
+                        serde_json::Deserializer::from_slice(&self.buf).into_iter::<Breach>();
+                    match items.next() {
+                        Some(Ok(breach)) => {
+                            let read = items.byte_offset();
+                            self.buf.drain(..read);
+                            return Ok(Some(breach));
+                        }
+                        Some(Err(e)) if !e.is_eof() => {
+                            return Err(HibpError::ParseError(e.to_string()));
+                        }
+                        // The item isn't complete yet, wait for more data
+                        _ => {}
+                    }
+                }
+            }
+
+            match self.body.next().await {
+                Some(chunk) => self.buf.extend_from_slice(&chunk?),
+                None => {
+                    return Err(HibpError::ParseError(
+                        "response ended before the breach list was complete".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+// ╔══════════════════════════════════════════════════════════════════════════════════════════════╗
+// ║                                                                                              ║
+// ║                                    ANALYSIS HELPERS                                          ║
+// ║                                                                                              ║
+// ╚══════════════════════════════════════════════════════════════════════════════════════════════╝
+
+/// Summarizes a list of pastes: how many there are, across how many sources, and roughly how many
+/// emails they exposed. An empty list (or one with no counts/sources) returns zeros.
+pub fn paste_summary(pastes: &[Paste]) -> PasteSummary {
+    let sources: std::collections::HashSet<&str> = pastes
+        .iter()
+        .filter_map(|p| p.source.as_deref())
+        .filter(|s| !s.trim().is_empty())
+        .collect();
+
+    PasteSummary {
+        paste_count: pastes.len(),
+        email_count: pastes.iter().map(|p| p.email_count.unwrap_or(0)).sum(),
+        source_count: sources.len(),
+    }
+}
+
+/// Returns how long ago the breach occurred, based on its `breach_date`.
+/// A breach dated after `now` (bad data or clock skew) is treated as zero days old.
+pub fn breach_age(breach: &Breach, now: NaiveDate) -> chrono::Duration {
+    (now - breach.breach_date).max(chrono::Duration::zero())
+}
+
+/// Returns true if the breach occurred at most `within` before `now`.
+pub fn is_recent(breach: &Breach, now: NaiveDate, within: chrono::Duration) -> bool {
+    breach_age(breach, now) <= within
+}
+
+/// Returns how much a breach's `pwn_count` changed between two observations of it (e.g. saved
+/// snapshots), negative if it shrank.
+///
+/// # Errors
+/// - Returns [`HibpError::BreachMismatch`] if `old` and `new` aren't the same breach (by name)
+pub fn pwn_count_delta(old: &Breach, new: &Breach) -> HibpResult<i64> {
+    if old.name != new.name {
+        return Err(HibpError::BreachMismatch(
+            old.name.clone(),
+            new.name.clone(),
+        ));
+    }
+    Ok(i64::try_from(new.pwn_count).unwrap_or(i64::MAX)
+        - i64::try_from(old.pwn_count).unwrap_or(i64::MAX))
+}
+
+/// Returns the names of the flags set on a breach, in a fixed order: "Verified", "Fabricated",
+/// "Sensitive", "Retired", "SpamList", "Malware", "SubscriptionFree", "StealerLog".
+pub fn active_flags(breach: &Breach) -> Vec<&'static str> {
+    [
+        (breach.is_verified, "Verified"),
+        (breach.is_fabricated, "Fabricated"),
+        (breach.is_sensitive, "Sensitive"),
+        (breach.is_retired, "Retired"),
+        (breach.is_spam_list, "SpamList"),
+        (breach.is_malware, "Malware"),
+        (breach.is_subscription_free, "SubscriptionFree"),
+        (breach.is_stealer_log, "StealerLog"),
+    ]
+    .into_iter()
+    .filter_map(|(set, name)| set.then_some(name))
+    .collect()
+}
+
+/// Groups breaches by the year of their `breach_date`, years in ascending order. Breaches keep
+/// their input order within a year.
+pub fn breaches_by_year(breaches: &[Breach]) -> std::collections::BTreeMap<i32, Vec<&Breach>> {
+    let mut by_year: std::collections::BTreeMap<i32, Vec<&Breach>> = Default::default();
+    for breach in breaches {
+        by_year
+            .entry(breach.breach_date.year())
+            .or_default()
+            .push(breach);
+    }
+    by_year
+}
+
+/// Returns the breaches of `current` that weren't in `previous` (matched by name), in `current`'s
+/// order. Breaches that disappeared since `previous` are ignored.
+pub fn diff_account_breaches(previous: &[Breach], current: &[Breach]) -> Vec<Breach> {
+    let known: std::collections::HashSet<&str> = previous.iter().map(|b| b.name.as_str()).collect();
+
+    current
+        .iter()
+        .filter(|b| !known.contains(b.name.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Compares two fetches of the subscribed domains (see [`HibpClient::get_subscribed_domains`]),
+/// e.g. to alert when a domain gained breached accounts. Domains are matched by name,
+/// case-insensitively, and a missing `pwn_count` counts as 0.
+pub fn diff_subscribed_domains(
+    old: &[SubscribedDomain],
+    new: &[SubscribedDomain],
+) -> DomainChanges {
+    let old_counts: HashMap<String, u64> = old
+        .iter()
+        .map(|d| (d.domain_name.to_ascii_lowercase(), d.pwn_count.unwrap_or(0)))
+        .collect();
+    let new_names: std::collections::HashSet<String> = new
+        .iter()
+        .map(|d| d.domain_name.to_ascii_lowercase())
+        .collect();
+
+    let mut changes = DomainChanges::default();
+    for domain in new {
+        match old_counts.get(&domain.domain_name.to_ascii_lowercase()) {
+            Some(&old_count) => {
+                let increase = domain.pwn_count.unwrap_or(0).saturating_sub(old_count);
+                if increase > 0 {
+                    changes.grown.push((domain.domain_name.clone(), increase));
+                }
+            }
+            None => changes.added.push(domain.domain_name.clone()),
+        }
+    }
+    changes.removed = old
+        .iter()
+        .filter(|d| !new_names.contains(&d.domain_name.to_ascii_lowercase()))
+        .map(|d| d.domain_name.clone())
+        .collect();
+    changes
+}
+
+/// Sensitivity of the data classes HIBP commonly reports, matched case-insensitively by
+/// [`data_classes_by_sensitivity`]. This table is the only place classes are classified; data
+/// classes not listed here are [`Sensitivity::Medium`].
+pub const SENSITIVITY_TABLE: &[(&str, Sensitivity)] = &[
+    // Credentials
+    ("Passwords", Sensitivity::High),
+    ("Password hints", Sensitivity::High),
+    ("Security questions and answers", Sensitivity::High),
+    ("Auth tokens", Sensitivity::High),
+    ("Historical passwords", Sensitivity::High),
+    ("PINs", Sensitivity::High),
+    // Financial
+    ("Credit cards", Sensitivity::High),
+    ("Credit card CVV", Sensitivity::High),
+    ("Partial credit card data", Sensitivity::High),
+    ("Bank account numbers", Sensitivity::High),
+    ("Financial transactions", Sensitivity::High),
+    // Identity documents
+    ("Social security numbers", Sensitivity::High),
+    ("Government issued IDs", Sensitivity::High),
+    ("Passport numbers", Sensitivity::High),
+    ("Tax numbers", Sensitivity::High),
+    // Personal details
+    ("Dates of birth", Sensitivity::Medium),
+    ("Physical addresses", Sensitivity::Medium),
+    ("Phone numbers", Sensitivity::Medium),
+    ("IP addresses", Sensitivity::Medium),
+    ("Geographic locations", Sensitivity::Medium),
+    ("Private messages", Sensitivity::Medium),
+    ("Health insurance information", Sensitivity::Medium),
+    ("Sexual orientations", Sensitivity::Medium),
+    // Contact and profile details
+    ("Email addresses", Sensitivity::Low),
+    ("Names", Sensitivity::Low),
+    ("Usernames", Sensitivity::Low),
+    ("Genders", Sensitivity::Low),
+    ("Website activity", Sensitivity::Low),
+    ("Avatars", Sensitivity::Low),
+    ("Job titles", Sensitivity::Low),
+    ("Employers", Sensitivity::Low),
+    ("Social media profiles", Sensitivity::Low),
+    ("Spoken languages", Sensitivity::Low),
+];
+
+/// Data classes that identify an account, which together with a password make a usable credential
+/// (see [`exposes_credentials`]).
+pub const IDENTITY_DATA_CLASSES: &[&str] = &["Email addresses", "Usernames", "Phone numbers"];
+
+/// Returns true if the breach exposes credentials: passwords together with something that
+/// identifies the account they belong to ([`IDENTITY_DATA_CLASSES`]). Such breaches allow account
+/// takeover through credential stuffing, unlike passwords leaked on their own.
+/// Data classes are matched case-insensitively.
+pub fn exposes_credentials(breach: &Breach) -> bool {
+    let exposes = |wanted: &str| {
+        breach
+            .data_classes
+            .iter()
+            .any(|class| class.trim().eq_ignore_ascii_case(wanted))
+    };
+
+    exposes("Passwords") && IDENTITY_DATA_CLASSES.iter().any(|class| exposes(class))
+}
+
+/// Scores how dangerous a breach is to the accounts in it, from 0 to 100, e.g. to list the most
+/// actionable breaches first.
+///
+/// Exposed credentials (see [`exposes_credentials`]) add 40, each [`Sensitivity::High`] data
+/// class 10 (at most 30), and a sensitive breach 10. Size adds up to 20 on a logarithmic scale,
+/// 2 per tenfold increase in `pwn_count`.
+pub fn breach_severity(breach: &Breach) -> u8 {
+    let credentials = if exposes_credentials(breach) { 40 } else { 0 };
+    let high_classes = breach
+        .data_classes
+        .iter()
+        .filter(|class| data_class_sensitivity(class) == Sensitivity::High)
+        .count()
+        .min(3) as u8
+        * 10;
+    let sensitive = if breach.is_sensitive { 10 } else { 0 };
+    let size = if breach.pwn_count == 0 {
+        0
+    } else {
+        ((breach.pwn_count as f64).log10() * 2.0).round().min(20.0) as u8
+    };
+    credentials + high_classes + sensitive + size
+}
+
+/// Returns the sensitivity of a single data class according to [`SENSITIVITY_TABLE`].
+pub fn data_class_sensitivity(data_class: &str) -> Sensitivity {
+    SENSITIVITY_TABLE
+        .iter()
+        .find(|(class, _)| class.eq_ignore_ascii_case(data_class.trim()))
+        .map_or(Sensitivity::Medium, |&(_, sensitivity)| sensitivity)
+}
+
+/// Classifies the breach's data classes with [`data_class_sensitivity`], most sensitive first.
+/// Classes of the same sensitivity keep the breach's order.
+pub fn data_classes_by_sensitivity(breach: &Breach) -> Vec<(String, Sensitivity)> {
+    let mut classified: Vec<(String, Sensitivity)> = breach
+        .data_classes
+        .iter()
+        .map(|class| (class.clone(), data_class_sensitivity(class)))
+        .collect();
+    classified.sort_by_key(|&(_, sensitivity)| std::cmp::Reverse(sensitivity));
+    classified
+}
+
+/// Returns a SHA-256 hex digest of the breach list's content, to tell after a sync whether anything
+/// changed (e.g. compared with the digest of the previously processed list).
+///
+/// Breaches are hashed sorted by name, each as its JSON form with fields in declaration order, so
+/// the digest depends only on the data: not on the list's order, and not on the run or platform.
+pub fn breaches_content_hash(breaches: &[Breach]) -> String {
+    use sha2::{Digest as _, Sha256};
+
+    let mut sorted: Vec<&Breach> = breaches.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut hasher = Sha256::new();
+    for breach in sorted {
+        // Serializing a plain struct can't fail
+        hasher.update(serde_json::to_vec(breach).unwrap_or_default());
+        hasher.update(b"\n");
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Parses a Pwned Passwords range response body into `(suffix, count)` pairs, in body order.
+///
+/// Suffixes are uppercased, and surrounding whitespace (including the `\r` of CRLF line endings)
+/// is ignored. Blank lines and malformed lines (no `:`, empty suffix, or a count that isn't a
+/// number) are skipped rather than failing the whole range. Padding entries (count 0) are kept.
+pub fn parse_range_body(body: &str) -> Vec<(String, u64)> {
+    body.lines()
+        .filter_map(|line| {
+            let (suffix, count) = line.trim().split_once(':')?;
+            let suffix = suffix.trim();
+            if suffix.is_empty() {
+                return None;
+            }
+            Some((suffix.to_ascii_uppercase(), count.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Reads a list of password hashes with their counts, one `SHA1:COUNT` entry per line: the format
+/// of HIBP's downloadable Pwned Passwords files, which are ordered by count, so their first lines
+/// make a list of the most common passwords (see [`HibpClient::change_common_passwords`]).
+/// Lines that aren't a full SHA1 hash with a count are skipped.
+pub fn load_password_hash_list(path: &std::path::Path) -> std::io::Result<HashMap<String, u64>> {
+    let body = std::fs::read_to_string(path)?;
+    Ok(parse_range_body(&body)
+        .into_iter()
+        .filter(|(hash, _)| hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .collect())
+}
+
+/// Count at and above which [`password_risk_score`] returns 100.
+const RISK_SATURATION_COUNT: f64 = 10_000_000.0;
+
+/// Maps a password's Pwned Passwords count onto a 0-100 risk score, e.g. for a gauge.
+///
+/// The scale is logarithmic: 0 occurrences score 0, a single one scores 1, and each tenfold
+/// increase adds about 14 points (10 -> 15, 1,000 -> 43, 1,000,000 -> 86), until the score
+/// saturates at 100 from 10,000,000 occurrences.
+pub fn password_risk_score(count: u64) -> u8 {
+    if count == 0 {
+        return 0;
+    }
+    let scaled = 1.0 + 99.0 * (count as f64).log10() / RISK_SATURATION_COUNT.log10();
+    scaled.round().min(100.0) as u8
+}
+
+/// Writes an email's breaches and pastes as one CSV report, breaches first.
+///
+/// Columns are `account` (`account` as given, so pass it through [`crate::shared::redact_email`]
+/// to mask it), `type` (`breach` or `paste`), `id` (breach name or paste ID), `name` (breach or
+/// paste title), `date`, `source` (breach domain or paste source) and `count` (accounts or
+/// emails). Missing paste fields are written as empty cells.
+pub fn account_report_to_csv(
+    account: &str,
+    breaches: &[Breach],
+    pastes: &[Paste],
+    mut writer: impl std::io::Write,
+) -> std::io::Result<()> {
+    /// Quotes a field if it holds a delimiter, quote or line break, doubling inner quotes.
+    fn escape(field: &str) -> std::borrow::Cow<'_, str> {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\"")).into()
+        } else {
+            field.into()
+        }
+    }
+
+    let mut write_row = |fields: [&str; 7]| -> std::io::Result<()> {
+        let row: Vec<_> = fields.into_iter().map(escape).collect();
+        writeln!(writer, "{}", row.join(","))
+    };
+
+    write_row(["account", "type", "id", "name", "date", "source", "count"])?;
+    for b in breaches {
+        write_row([
+            account,
+            "breach",
+            &b.name,
+            &b.title,
+            &b.breach_date.to_string(),
+            &b.domain,
+            &b.pwn_count.to_string(),
+        ])?;
+    }
+    for p in pastes {
+        write_row([
+            account,
+            "paste",
+            p.id.as_deref().unwrap_or(""),
+            p.title.as_deref().unwrap_or(""),
+            &p.date.map(|d| d.to_rfc3339()).unwrap_or_default(),
+            p.source.as_deref().unwrap_or(""),
+            &p.email_count.map(|c| c.to_string()).unwrap_or_default(),
+        ])?;
+    }
+    Ok(())
+}
+
+/// Escapes the characters HTML treats specially, for text placed in an element or attribute.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Writes breaches to an SQLite database at `path` (created if needed), for querying them with SQL.
+///
+/// Creates a `breaches` table (one row per breach, keyed by `name`, dates as ISO 8601 text) and a
+/// `breach_data_classes` table (`breach_name`, `data_class`). Both tables are dropped and
+/// recreated first, so exporting again replaces the previous data. Everything is written in
+/// one transaction: a failed export leaves the database as it was.
+#[cfg(feature = "sqlite")]
+pub fn export_breaches_to_sqlite(
+    breaches: &[Breach],
+    path: &std::path::Path,
+) -> rusqlite::Result<()> {
+    let mut conn = rusqlite::Connection::open(path)?;
+    let tx = conn.transaction()?;
+
+    tx.execute_batch(
+        "DROP TABLE IF EXISTS breach_data_classes;
+         DROP TABLE IF EXISTS breaches;
+         CREATE TABLE breaches (
+             name TEXT PRIMARY KEY,
+             title TEXT NOT NULL,
+             domain TEXT NOT NULL,
+             breach_date TEXT NOT NULL,
+             added_date TEXT,
+             modified_date TEXT,
+             pwn_count INTEGER NOT NULL,
+             description TEXT NOT NULL,
+             is_verified INTEGER NOT NULL,
+             is_fabricated INTEGER NOT NULL,
+             is_sensitive INTEGER NOT NULL,
+             is_retired INTEGER NOT NULL,
+             is_spam_list INTEGER NOT NULL,
+             is_malware INTEGER NOT NULL,
+             is_subscription_free INTEGER NOT NULL,
+             is_stealer_log INTEGER NOT NULL,
+             logo_path TEXT,
+             attribution TEXT
+         );
+         CREATE TABLE breach_data_classes (
+             breach_name TEXT NOT NULL REFERENCES breaches(name),
+             data_class TEXT NOT NULL,
+             PRIMARY KEY (breach_name, data_class)
+         );",
+    )?;
+
+    {
+        let mut insert_breach = tx.prepare(
+            "INSERT OR REPLACE INTO breaches VALUES
+             (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        )?;
+        let mut insert_class =
+            tx.prepare("INSERT OR IGNORE INTO breach_data_classes VALUES (?1, ?2)")?;
+
+        for b in breaches {
+            insert_breach.execute(rusqlite::params![
+                b.name,
+                b.title,
+                b.domain,
+                b.breach_date.to_string(),
+                b.added_date.map(|d| d.to_rfc3339()),
+                b.modified_date.map(|d| d.to_rfc3339()),
+                // SQLite integers are signed; no breach comes near the limit
+                i64::try_from(b.pwn_count).unwrap_or(i64::MAX),
+                b.description,
+                b.is_verified,
+                b.is_fabricated,
+                b.is_sensitive,
+                b.is_retired,
+                b.is_spam_list,
+                b.is_malware,
+                b.is_subscription_free,
+                b.is_stealer_log,
+                b.logo_path,
+                b.attribution,
+            ])?;
+            for class in &b.data_classes {
+                insert_class.execute(rusqlite::params![b.name, class])?;
+            }
+        }
+    }
+
+    tx.commit()
+}
+
+/// Finds passwords shared by several accounts in a `(username, password)` list, offline.
+///
+/// Returns `(sha1, usernames)` pairs for every password used by more than one entry, keyed by the
+/// uppercase SHA1 hex of the password (the same form [`HibpClient::check_password`] hashes to) so
+/// the plaintext isn't kept in the result. Most shared passwords come first, then by hash;
+/// usernames keep their input order.
+pub fn find_reused_passwords(entries: &[(String, String)]) -> Vec<(String, Vec<String>)> {
+    let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+    for (username, password) in entries {
+        let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+        by_hash.entry(hash).or_default().push(username.clone());
+    }
+
+    let mut reused: Vec<(String, Vec<String>)> = by_hash
+        .into_iter()
+        .filter(|(_, usernames)| usernames.len() > 1)
+        .collect();
+    reused.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+    reused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscription(rpm: u64) -> SubscriptionStatus {
+        SubscriptionStatus {
+            sub_name: "Pwned 1".to_string(),
+            description: String::new(),
+            sub_until: Utc::now(),
+            rpm,
+            domain_search_max_breached_accounts: None,
+            includes_stealer_logs: None,
+        }
+    }
+
+    /// A verified breach of `<name>.com` on 2020-01-01 with 1,000 accounts and `data_classes`.
+    fn breach(name: &str, data_classes: &[&str]) -> Breach {
+        Breach {
+            name: name.to_string(),
+            title: name.to_string(),
+            domain: format!("{}.com", name.to_ascii_lowercase()),
+            breach_date: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+            added_date: None,
+            modified_date: None,
+            pwn_count: 1_000,
+            description: String::new(),
+            data_classes: data_classes.iter().map(|class| class.to_string()).collect(),
+            is_verified: true,
+            is_fabricated: false,
+            is_sensitive: false,
+            is_retired: false,
+            is_spam_list: false,
+            is_malware: false,
+            is_subscription_free: false,
+            is_stealer_log: false,
+            logo_path: None,
+            attribution: None,
+        }
+    }
+
+    fn domain(name: &str, pwn_count: Option<u64>) -> SubscribedDomain {
+        SubscribedDomain {
+            domain_name: name.to_string(),
+            pwn_count,
+            pwn_count_excluding_spam_lists: None,
+            pwn_count_excl_spam_lists_at_last_subscription_renewal: None,
+            next_subscription_renewal: None,
+        }
+    }
+
+    fn paste(source: Option<&str>, email_count: Option<u64>) -> Paste {
+        Paste {
+            source: source.map(str::to_string),
+            id: None,
+            title: None,
+            date: None,
+            email_count,
+        }
+    }
+
+    /// Returns an empty directory unique to the test `name`.
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("emicon-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Returns a client whose requests go through a local proxy that refuses every connection,
+    /// and the `CONNECT` targets the proxy was asked for, so tests can tell whether HIBP would
+    /// have been queried without touching the network.
+    async fn offline_client() -> (HibpClient, Arc<Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let targets = Arc::new(Mutex::new(Vec::new()));
+        let seen = targets.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut head = [0; 1024];
+                let read = stream.read(&mut head).await.unwrap_or(0);
+                let head = String::from_utf8_lossy(&head[..read]);
+                if let Some(target) = head.strip_prefix("CONNECT ") {
+                    let target = target.split_whitespace().next().unwrap_or_default();
+                    seen.lock().unwrap().push(target.to_string());
+                }
+                let _ = stream.write_all(b"HTTP/1.1 502 Bad Gateway\r\n\r\n").await;
+            }
+        });
+
+        let mut client = HibpClient::new("emicon-tests".to_string(), 5).unwrap();
+        client.client = Client::builder()
+            .proxy(reqwest::Proxy::all(proxy).unwrap())
+            .build()
+            .unwrap();
+        (client, targets)
+    }
+
+    fn unknown(status: u16) -> HibpError {
+        HibpError::Unknown {
+            status: StatusCode::from_u16(status).unwrap(),
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn transient_failures_are_retryable() {
+        assert!(HibpError::RateLimit { retry_after: None }.is_retryable());
+        assert!(HibpError::ServiceUnavailable { retry_after: None }.is_retryable());
+        assert!(HibpError::Maintenance { retry_after: None }.is_retryable());
+        assert!(HibpError::Maintenance {
+            retry_after: Some(std::time::Duration::from_secs(60))
+        }
+        .is_retryable());
+        assert!(HibpError::Timeout.is_retryable());
+        assert!(unknown(500).is_retryable());
+        assert!(unknown(502).is_retryable());
+    }
+
+    #[test]
+    fn terminal_failures_are_not_retryable() {
+        assert!(!HibpError::ClientBuildError("no TLS backend".to_string()).is_retryable());
+        assert!(!HibpError::NotFound.is_retryable());
+        assert!(!HibpError::Unauthorized.is_retryable());
+        assert!(!HibpError::Forbidden.is_retryable());
+        assert!(!HibpError::BannedUserAgent.is_retryable());
+        assert!(!HibpError::BadRequest.is_retryable());
+        assert!(!HibpError::ParseError("expected a list".to_string()).is_retryable());
+        assert!(!HibpError::InvalidHash("5BAA6".to_string()).is_retryable());
+        assert!(!HibpError::UnexpectedContentType("text/html".to_string()).is_retryable());
+        assert!(
+            !HibpError::RetryBudgetExhausted(Box::new(HibpError::RateLimit { retry_after: None }))
+                .is_retryable()
+        );
+        assert!(
+            !HibpError::BreachMismatch("Adobe".to_string(), "LinkedIn".to_string()).is_retryable()
+        );
+        assert!(!unknown(418).is_retryable());
+    }
+
+    #[tokio::test]
+    async fn request_errors_are_retryable_only_for_timeouts_and_connection_failures() {
+        let client = Client::new();
+
+        let invalid = client.get("not a url").send().await.unwrap_err();
+        assert!(!HibpError::ReqwestError(invalid).is_retryable());
+
+        // Nothing listens on a port that was just released
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let refused = client
+            .get(format!("http://127.0.0.1:{port}/"))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(HibpError::ReqwestError(refused).is_retryable());
+
+        // Accepts the connection but never answers
+        let silent = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", silent.local_addr().unwrap());
+        let timed_out = client
+            .get(url)
+            .timeout(std::time::Duration::from_millis(100))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(HibpError::ReqwestError(timed_out).is_retryable());
+    }
+
+    #[test]
+    fn recommended_bucket_stays_within_the_per_minute_limit() {
+        for rpm in [2, 10, 50, 100, 500, 1000, 12_000] {
+            let (capacity, refill_rate) = subscription(rpm).recommended_bucket().unwrap();
+            assert!(capacity >= 1 && refill_rate > 0.0, "rpm {rpm}");
+            let first_minute = capacity as f64 + refill_rate * 60.0;
+            assert!((first_minute - rpm as f64).abs() < 1e-9, "rpm {rpm}");
+        }
+    }
+
+    #[test]
+    fn recommended_bucket_rejects_limits_without_refill() {
+        assert_eq!(subscription(0).recommended_bucket(), None);
+        assert_eq!(subscription(1).recommended_bucket(), None);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_export_replaces_previous_exports() {
+        let path = std::env::temp_dir().join(format!("emicon-{}-breaches.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let breaches = [
+            breach("Adobe", &["Email addresses", "Passwords", "Password hints"]),
+            breach("LinkedIn", &["Email addresses", "Passwords"]),
+            breach("Gawker", &[]),
+        ];
+
+        export_breaches_to_sqlite(&breaches, &path).unwrap();
+        export_breaches_to_sqlite(&breaches, &path).unwrap();
+
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        assert_eq!(count("breaches"), 3);
+        assert_eq!(count("breach_data_classes"), 5);
+        let (domain, pwn_count, is_verified): (String, i64, bool) = conn
+            .query_row(
+                "SELECT domain, pwn_count, is_verified FROM breaches WHERE name = 'Adobe'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (domain.as_str(), pwn_count, is_verified),
+            ("adobe.com", 1_000, true)
+        );
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn parse_range_body_reads_entries_in_order() {
+        let body = "0018A45C4D1DEF81644B54AB7F969B88D65:1\n00D4F6E8FA6EECAD2A3AA415EEC418D38EC:2";
+        assert_eq!(
+            parse_range_body(body),
+            [
+                ("0018A45C4D1DEF81644B54AB7F969B88D65".to_string(), 1),
+                ("00D4F6E8FA6EECAD2A3AA415EEC418D38EC".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_range_body_handles_crlf_and_case() {
+        let body =
+            "0018a45c4d1def81644b54ab7f969b88d65:10\r\n00D4F6E8FA6EECAD2A3AA415EEC418D38EC:3\r\n";
+        assert_eq!(
+            parse_range_body(body),
+            [
+                ("0018A45C4D1DEF81644B54AB7F969B88D65".to_string(), 10),
+                ("00D4F6E8FA6EECAD2A3AA415EEC418D38EC".to_string(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_range_body_skips_blank_and_malformed_lines() {
+        let body = "\n\
+            AAAA:1\n\
+            \r\n   \n\
+            no separator\n\
+            :5\n\
+            BBBB:many\n\
+            CCCC:-1\n\
+            DDDD:\n\
+             EEEE : 7 \n";
+        assert_eq!(
+            parse_range_body(body),
+            [("AAAA".to_string(), 1), ("EEEE".to_string(), 7)]
+        );
+    }
+
+    #[test]
+    fn parse_range_body_keeps_padding_entries() {
+        assert_eq!(
+            parse_range_body("AAAA:0\nBBBB:4"),
+            [("AAAA".to_string(), 0), ("BBBB".to_string(), 4)]
+        );
+        assert!(parse_range_body("").is_empty());
+    }
+
+    #[test]
+    fn found_count_treats_zero_as_padding_only_when_padded() {
+        assert_eq!(HibpClient::found_count(0, true), None);
+        assert_eq!(HibpClient::found_count(0, false), Some(0));
+        assert_eq!(HibpClient::found_count(3, true), Some(3));
+        assert_eq!(HibpClient::found_count(3, false), Some(3));
+    }
+
+    #[test]
+    fn match_suffix_ignores_padding_in_padded_responses() {
+        let body = "AAAA:0\r\nBBBB:12\r\n";
+        assert_eq!(HibpClient::match_suffix(body, "aaaa", true), None);
+        assert_eq!(HibpClient::match_suffix(body, "aaaa", false), Some(0));
+        assert_eq!(HibpClient::match_suffix(body, "bbbb", true), Some(12));
+        assert_eq!(HibpClient::match_suffix(body, "CCCC", true), None);
+        assert_eq!(HibpClient::match_suffix(body, "CCCC", false), None);
+    }
+
+    #[test]
+    fn find_reused_passwords_groups_shared_passwords() {
+        let entries: Vec<(String, String)> = [
+            ("alice", "password"),
+            ("bob", "123456"),
+            ("carol", "password"),
+            ("dave", "unique"),
+            ("erin", "123456"),
+            ("frank", "password"),
+            ("grace", "Password"),
+        ]
+        .into_iter()
+        .map(|(user, pass)| (user.to_string(), pass.to_string()))
+        .collect();
+
+        assert_eq!(
+            find_reused_passwords(&entries),
+            [
+                (
+                    "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8".to_string(),
+                    vec![
+                        "alice".to_string(),
+                        "carol".to_string(),
+                        "frank".to_string()
+                    ],
+                ),
+                (
+                    "7C4A8D09CA3762AF61E59520943DC26494F8941B".to_string(),
+                    vec!["bob".to_string(), "erin".to_string()],
+                ),
+            ]
+        );
+        assert!(find_reused_passwords(&entries[3..5]).is_empty());
+    }
+
+    #[test]
+    fn active_flags_lists_every_combination_in_order() {
+        const NAMES: [&str; 8] = [
+            "Verified",
+            "Fabricated",
+            "Sensitive",
+            "Retired",
+            "SpamList",
+            "Malware",
+            "SubscriptionFree",
+            "StealerLog",
+        ];
+        for mask in 0u8..=u8::MAX {
+            let set = |bit: usize| mask & (1 << bit) != 0;
+            let mut flagged = breach("Adobe", &[]);
+            flagged.is_verified = set(0);
+            flagged.is_fabricated = set(1);
+            flagged.is_sensitive = set(2);
+            flagged.is_retired = set(3);
+            flagged.is_spam_list = set(4);
+            flagged.is_malware = set(5);
+            flagged.is_subscription_free = set(6);
+            flagged.is_stealer_log = set(7);
+
+            let expected: Vec<&str> = (0..NAMES.len())
+                .filter(|&bit| set(bit))
+                .map(|bit| NAMES[bit])
+                .collect();
+            assert_eq!(active_flags(&flagged), expected, "mask {mask:#010b}");
+        }
+    }
+
+    #[test]
+    fn account_report_to_csv_quotes_fields_and_leaves_missing_cells_empty() {
+        let mut quoted = breach("Acme", &[]);
+        quoted.title = "Acme, \"Inc\"\nForums".to_string();
+        let breaches = [breach("Adobe", &[]), quoted];
+        let pastes = [
+            Paste {
+                source: Some("Pastebin".to_string()),
+                id: Some("8Q0BvKD8".to_string()),
+                title: Some("dump, part 1".to_string()),
+                date: Some("2014-03-04T19:14:54Z".parse().unwrap()),
+                email_count: Some(139),
+            },
+            Paste {
+                source: Some("AdHocUrl".to_string()),
+                id: Some("http://example.com/leak.txt".to_string()),
+                title: None,
+                date: None,
+                email_count: None,
+            },
+        ];
+
+        let mut out = Vec::new();
+        account_report_to_csv("t***@example.com", &breaches, &pastes, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "account,type,id,name,date,source,count\n\
+             t***@example.com,breach,Adobe,Adobe,2020-01-01,adobe.com,1000\n\
+             t***@example.com,breach,Acme,\"Acme, \"\"Inc\"\"\nForums\",2020-01-01,acme.com,1000\n\
+             t***@example.com,paste,8Q0BvKD8,\"dump, part 1\",2014-03-04T19:14:54+00:00,Pastebin,139\n\
+             t***@example.com,paste,http://example.com/leak.txt,,,AdHocUrl,\n"
+        );
+    }
+
+    #[test]
+    fn sensitivity_table_has_no_duplicate_classes() {
+        for (i, (class, _)) in SENSITIVITY_TABLE.iter().enumerate() {
+            assert!(
+                !SENSITIVITY_TABLE[i + 1..]
+                    .iter()
+                    .any(|(other, _)| other.eq_ignore_ascii_case(class)),
+                "{class} is listed twice"
+            );
+        }
+    }
+
+    #[test]
+    fn data_class_sensitivity_matches_table_case_insensitively() {
+        for &(class, sensitivity) in SENSITIVITY_TABLE {
+            assert_eq!(data_class_sensitivity(class), sensitivity, "{class}");
+            assert_eq!(
+                data_class_sensitivity(&class.to_ascii_uppercase()),
+                sensitivity
+            );
+        }
+        assert_eq!(data_class_sensitivity("passwords"), Sensitivity::High);
+        assert_eq!(
+            data_class_sensitivity(" email ADDRESSES "),
+            Sensitivity::Low
+        );
+    }
+
+    #[test]
+    fn data_class_sensitivity_defaults_to_medium() {
+        assert_eq!(
+            data_class_sensitivity("Astrological signs"),
+            Sensitivity::Medium
+        );
+        assert_eq!(data_class_sensitivity(""), Sensitivity::Medium);
+    }
+
+    #[test]
+    fn data_classes_by_sensitivity_sorts_most_sensitive_first() {
+        let classified = data_classes_by_sensitivity(&breach(
+            "Adobe",
+            &[
+                "Email addresses",
+                "Dates of birth",
+                "Passwords",
+                "Usernames",
+                "Astrological signs",
+                "password hints",
+            ],
+        ));
+        assert_eq!(
+            classified,
+            [
+                ("Passwords".to_string(), Sensitivity::High),
+                ("password hints".to_string(), Sensitivity::High),
+                ("Dates of birth".to_string(), Sensitivity::Medium),
+                ("Astrological signs".to_string(), Sensitivity::Medium),
+                ("Email addresses".to_string(), Sensitivity::Low),
+                ("Usernames".to_string(), Sensitivity::Low),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_subscribed_domains_reports_growth_additions_and_removals() {
+        let old = [
+            domain("example.com", Some(10)),
+            domain("shrunk.org", Some(50)),
+            domain("gone.net", Some(3)),
+        ];
+        let new = [
+            domain("new.io", Some(1)),
+            domain("shrunk.org", Some(40)),
+            domain("example.com", Some(15)),
+        ];
+        assert_eq!(
+            diff_subscribed_domains(&old, &new),
+            DomainChanges {
+                grown: vec![("example.com".to_string(), 5)],
+                added: vec!["new.io".to_string()],
+                removed: vec!["gone.net".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn diff_subscribed_domains_matches_names_case_insensitively() {
+        let old = [domain("Example.com", Some(10))];
+        let new = [domain("EXAMPLE.COM", Some(12))];
+        assert_eq!(
+            diff_subscribed_domains(&old, &new),
+            DomainChanges {
+                grown: vec![("EXAMPLE.COM".to_string(), 2)],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn diff_subscribed_domains_counts_missing_pwn_count_as_zero() {
+        let old = [domain("a.com", None), domain("b.com", Some(4))];
+        let new = [domain("a.com", Some(7)), domain("b.com", None)];
+        assert_eq!(
+            diff_subscribed_domains(&old, &new),
+            DomainChanges {
+                grown: vec![("a.com".to_string(), 7)],
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            diff_subscribed_domains(&new, &new),
+            DomainChanges::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn corrupt_data_classes_cache_is_downloaded_again() {
+        let dir = temp_dir("dataclasses-cache");
+        let path = dir.join("dataclasses.json");
+        let ttl = std::time::Duration::from_secs(3600);
+        let (client, targets) = offline_client().await;
+
+        std::fs::write(&path, br#"["Passwords","Email addr"#).unwrap();
+        assert!(read_json_cache::<Vec<String>>(&path, ttl).await.is_none());
+        // Nothing usable is cached, so the failed download is reported rather than hidden
+        assert!(client.get_data_classes_cached(&dir, ttl).await.is_err());
+        assert_eq!(*targets.lock().unwrap(), ["haveibeenpwned.com:443"]);
+
+        std::fs::write(&path, br#"["Passwords"]"#).unwrap();
+        let classes = client.get_data_classes_cached(&dir, ttl).await.unwrap();
+        assert_eq!(classes, ["Passwords"]);
+        assert_eq!(targets.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn corrupt_account_cache_is_downloaded_again() {
+        let dir = temp_dir("account-cache");
+        let email = "test@example.com";
+        let path = dir.join(format!("{}.json", crate::shared::email_fingerprint(email)));
+        let (mut client, targets) = offline_client().await;
+        client.change_account_cache(Some(dir), std::time::Duration::from_secs(3600));
+
+        std::fs::write(&path, b"not json").unwrap();
+        assert!(client.get_account_breaches(email).await.is_err());
+        assert_eq!(*targets.lock().unwrap(), ["haveibeenpwned.com:443"]);
+
+        let cached = [breach("Adobe", &["Passwords"])];
+        std::fs::write(&path, serde_json::to_vec(&cached).unwrap()).unwrap();
+        let breaches = client.get_account_breaches(email).await.unwrap();
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].name, "Adobe");
+        assert_eq!(targets.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn retry_after_is_read_from_transient_failures() {
+        let wait = Some(std::time::Duration::from_secs(30));
+        for error in [
+            HibpError::RateLimit { retry_after: wait },
+            HibpError::ServiceUnavailable { retry_after: wait },
+            HibpError::Maintenance { retry_after: wait },
+        ] {
+            assert_eq!(error.retry_after(), wait, "{error:?}");
+        }
+        assert!(HibpError::RateLimit { retry_after: None }
+            .retry_after()
+            .is_none());
+        assert!(HibpError::Timeout.retry_after().is_none());
+        assert!(unknown(500).retry_after().is_none());
+    }
+
+    #[tokio::test]
+    async fn check_password_pair_consults_common_passwords_for_shared_prefixes() {
+        // Both hash to a DC0A5 prefix
+        let (a, b) = ("pw2001", "pw2501");
+        let (mut client, targets) = offline_client().await;
+        client.change_common_passwords(Some(HashMap::from([
+            ("DC0A55411C0E50F210B61C9B5287F642349ADE0F".to_string(), 7),
+            ("DC0A57B817B98AB3A598850A5F234D92E89138D1".to_string(), 3),
+        ])));
+
+        assert_eq!(client.check_password_pair(a, b).await.unwrap(), (7, 3));
+        assert!(targets.lock().unwrap().is_empty());
+
+        // Only the password missing from the list is looked up, once
+        client.change_common_passwords(Some(HashMap::from([(
+            "DC0A55411C0E50F210B61C9B5287F642349ADE0F".to_string(),
+            7,
+        )])));
+        assert!(client.check_password_pair(a, b).await.is_err());
+        assert_eq!(targets.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn breach_exists_is_case_sensitive_with_a_cold_cache() {
+        let (client, _) = offline_client().await;
+        // What HIBP answers for either spelling, as it matches names case-insensitively
+        for name in ["Adobe", "adobe"] {
+            client
+                .breach_details_cache
+                .lock()
+                .unwrap()
+                .insert(name.to_string(), breach("Adobe", &[]));
+        }
+
+        assert!(client.breach_exists("Adobe").await.unwrap());
+        assert!(!client.breach_exists("adobe").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn breach_exists_is_case_sensitive_with_a_warm_cache() {
+        let (client, targets) = offline_client().await;
+        client.breach_cache.lock().unwrap().breaches = Some(vec![breach("Adobe", &[])]);
+
+        assert!(client.breach_exists("Adobe").await.unwrap());
+        assert!(!client.breach_exists("adobe").await.unwrap());
+        assert!(!client.breach_exists("LinkedIn").await.unwrap());
+        assert!(targets.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn check_password_hash_rejects_malformed_hashes() {
+        let (client, targets) = offline_client().await;
+        for hash in [
+            "",
+            "5BAA",
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD",
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8A",
+            "ZBAA61E4C9B93F3F0682250B6CF8331B7EE68FD8",
+            "éBAA61E4C9B93F3F0682250B6CF8331B7EE68FD",
+        ] {
+            assert!(
+                matches!(
+                    client.check_password_hash(hash).await,
+                    Err(HibpError::InvalidHash(_))
+                ),
+                "{hash}"
+            );
+        }
+        assert!(targets.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn check_password_hash_looks_up_lowercase_hashes_in_the_common_list() {
+        let (mut client, targets) = offline_client().await;
+        client.change_common_passwords(Some(HashMap::from([(
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8".to_string(),
+            9,
+        )])));
+
+        let count = client
+            .check_password_hash("5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8")
+            .await;
+        assert_eq!(count.unwrap(), 9);
+        assert!(targets.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn password_risk_score_boundaries() {
+        assert_eq!(password_risk_score(0), 0);
+        assert_eq!(password_risk_score(1), 1);
+        assert_eq!(password_risk_score(1_000), 43);
+        assert_eq!(password_risk_score(RISK_SATURATION_COUNT as u64), 100);
+        assert_eq!(password_risk_score(RISK_SATURATION_COUNT as u64 * 10), 100);
+        assert_eq!(password_risk_score(u64::MAX), 100);
+    }
+
+    #[test]
+    fn password_risk_score_grows_with_the_count() {
+        let scores: Vec<u8> = [1, 10, 100, 10_000, 1_000_000, 10_000_000]
+            .into_iter()
+            .map(password_risk_score)
+            .collect();
+        assert!(
+            scores.windows(2).all(|pair| pair[0] < pair[1]),
+            "{scores:?}"
+        );
+    }
+
+    #[test]
+    fn paste_summary_of_nothing_is_zero() {
+        assert_eq!(paste_summary(&[]), PasteSummary::default());
+        assert_eq!(
+            paste_summary(&[paste(None, None), paste(None, None)]),
+            PasteSummary {
+                paste_count: 2,
+                email_count: 0,
+                source_count: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn paste_summary_counts_distinct_named_sources() {
+        let pastes = [
+            paste(Some("Pastebin"), Some(100)),
+            paste(Some("Pastebin"), Some(40)),
+            paste(Some(""), Some(1)),
+            paste(Some("   "), None),
+            paste(Some("AdHocUrl"), Some(1_099)),
+            paste(None, Some(0)),
+        ];
+        assert_eq!(
+            paste_summary(&pastes),
+            PasteSummary {
+                paste_count: 6,
+                email_count: 1_240,
+                source_count: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn diff_account_breaches_returns_only_new_breaches() {
+        let previous = [breach("Adobe", &[]), breach("Dropbox", &[])];
+        let current = [
+            breach("LinkedIn", &[]),
+            breach("Adobe", &[]),
+            breach("Canva", &[]),
+        ];
+        let names: Vec<String> = diff_account_breaches(&previous, &current)
+            .into_iter()
+            .map(|b| b.name)
+            .collect();
+        // Dropbox disappeared, which isn't reported
+        assert_eq!(names, ["LinkedIn", "Canva"]);
+    }
+
+    #[test]
+    fn diff_account_breaches_of_unchanged_lists_is_empty() {
+        let breaches = [breach("Adobe", &[]), breach("Dropbox", &[])];
+        assert!(diff_account_breaches(&breaches, &breaches).is_empty());
+        assert!(diff_account_breaches(&breaches, &breaches[..1]).is_empty());
+        assert!(diff_account_breaches(&breaches, &[]).is_empty());
+    }
+
+    #[test]
+    fn exposes_credentials_needs_passwords_and_an_identity() {
+        assert!(exposes_credentials(&breach(
+            "Adobe",
+            &["Email addresses", "Passwords"]
+        )));
+        assert!(exposes_credentials(&breach(
+            "Adobe",
+            &["passwords", "Usernames"]
+        )));
+        assert!(!exposes_credentials(&breach("Adobe", &["Passwords"])));
+        assert!(!exposes_credentials(&breach(
+            "Adobe",
+            &["Email addresses", "Names"]
+        )));
+        assert!(!exposes_credentials(&breach(
+            "Adobe",
+            &["Email addresses", "Password hints"]
+        )));
+        assert!(!exposes_credentials(&breach("Adobe", &[])));
+    }
+
+    #[test]
+    fn pwn_count_delta_reports_growth_and_shrinkage() {
+        let old = breach("Adobe", &[]);
+        let mut grown = breach("Adobe", &[]);
+        grown.pwn_count = 1_500;
+        let mut shrunk = breach("Adobe", &[]);
+        shrunk.pwn_count = 400;
+
+        assert_eq!(pwn_count_delta(&old, &grown).unwrap(), 500);
+        assert_eq!(pwn_count_delta(&old, &shrunk).unwrap(), -600);
+        assert_eq!(pwn_count_delta(&old, &old).unwrap(), 0);
+    }
+
+    #[test]
+    fn pwn_count_delta_rejects_different_breaches() {
+        match pwn_count_delta(&breach("Adobe", &[]), &breach("LinkedIn", &[])) {
+            Err(HibpError::BreachMismatch(old, new)) => {
+                assert_eq!((&*old, &*new), ("Adobe", "LinkedIn"))
+            }
+            result => panic!("expected a mismatch, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn breaches_content_hash_ignores_order() {
+        let breaches = [
+            breach("Adobe", &["Passwords"]),
+            breach("Canva", &[]),
+            breach("LinkedIn", &["Email addresses"]),
+        ];
+        let permuted = [
+            breaches[2].clone(),
+            breaches[0].clone(),
+            breaches[1].clone(),
+        ];
+
+        let hash = breaches_content_hash(&breaches);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(breaches_content_hash(&permuted), hash);
+        assert_eq!(breaches_content_hash(&breaches), hash);
+    }
+
+    #[test]
+    fn breaches_content_hash_changes_with_any_field() {
+        let breaches = vec![breach("Adobe", &["Passwords"]), breach("Canva", &[])];
+        let hash = breaches_content_hash(&breaches);
+
+        let mut grown = breaches.clone();
+        grown[1].pwn_count += 1;
+        assert_ne!(breaches_content_hash(&grown), hash);
+
+        let mut flagged = breaches.clone();
+        flagged[0].is_sensitive = true;
+        assert_ne!(breaches_content_hash(&flagged), hash);
+
+        assert_ne!(breaches_content_hash(&breaches[..1]), hash);
+    }
+
+    #[test]
+    fn breaches_by_year_groups_in_year_order() {
+        let dated = |name: &str, year: i32, month: u32| {
+            let mut b = breach(name, &[]);
+            b.breach_date = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+            b
+        };
+        let breaches = [
+            dated("LinkedIn", 2016, 5),
+            dated("Adobe", 2013, 10),
+            dated("Dropbox", 2016, 1),
+            dated("Canva", 2019, 5),
+        ];
+
+        let by_year = breaches_by_year(&breaches);
+        let grouped: Vec<(i32, Vec<&str>)> = by_year
+            .iter()
+            .map(|(year, group)| (*year, group.iter().map(|b| b.name.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            grouped,
+            [
+                (2013, vec!["Adobe"]),
+                (2016, vec!["LinkedIn", "Dropbox"]),
+                (2019, vec!["Canva"]),
+            ]
+        );
+        assert!(breaches_by_year(&[]).is_empty());
+    }
+
+    #[test]
+    fn breach_age_counts_from_the_breach_date() {
+        // Breached on 2020-01-01
+        let adobe = breach("Adobe", &[]);
+        let now = NaiveDate::from_ymd_opt(2020, 3, 1).unwrap();
+        assert_eq!(breach_age(&adobe, now), chrono::Duration::days(60));
+        assert!(is_recent(&adobe, now, chrono::Duration::days(60)));
+        assert!(!is_recent(&adobe, now, chrono::Duration::days(59)));
+    }
+
+    #[test]
+    fn future_dated_breaches_are_zero_days_old() {
+        let adobe = breach("Adobe", &[]);
+        let now = NaiveDate::from_ymd_opt(2019, 12, 1).unwrap();
+        assert_eq!(breach_age(&adobe, now), chrono::Duration::zero());
+        assert!(is_recent(&adobe, now, chrono::Duration::zero()));
+    }
+
+    #[test]
+    fn days_until_expiry_is_negative_once_expired() {
+        let mut status = subscription(10);
+        let now: DateTime<Utc> = "2025-06-01T12:00:00Z".parse().unwrap();
+
+        status.sub_until = now - chrono::Duration::minutes(1);
+        assert_eq!(status.days_until_expiry(now), -1);
+        status.sub_until = now - chrono::Duration::days(3);
+        assert_eq!(status.days_until_expiry(now), -3);
+        status.sub_until = now + chrono::Duration::hours(23);
+        assert_eq!(status.days_until_expiry(now), 0);
+        status.sub_until = now + chrono::Duration::days(30) + chrono::Duration::hours(5);
+        assert_eq!(status.days_until_expiry(now), 30);
+    }
+
+    #[test]
+    fn is_expiring_soon_at_the_threshold() {
+        let mut status = subscription(10);
+        let now: DateTime<Utc> = "2025-06-01T12:00:00Z".parse().unwrap();
+
+        status.sub_until = now + chrono::Duration::days(7);
+        assert!(!status.is_expiring_soon(now, 7));
+        assert!(status.is_expiring_soon(now, 8));
+
+        status.sub_until = now + chrono::Duration::days(7) - chrono::Duration::seconds(1);
+        assert!(status.is_expiring_soon(now, 7));
 
-        Ok(count)
+        status.sub_until = now - chrono::Duration::minutes(1);
+        assert!(status.is_expiring_soon(now, 0));
     }
 }
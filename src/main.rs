@@ -2,49 +2,435 @@
 
 slint::include_modules!();
 
+#[cfg(feature = "blocking")]
+mod blocking;
+mod config;
 mod hibp;
-
-use hibp::{HibpClient, HibpError};
-use slint::{ModelRc, SharedString, VecModel};
+mod rate_limiter;
+mod shared;
+
+use config::{Config, WindowState};
+use hibp::{paste_summary, GlobalStats, HibpClient, HibpError, HibpResult};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use shared::{
+    format_breach_date, format_count, html_to_text, is_disposable_email, redact_email, BreachOrder,
+    EmiconError, EmiconResult,
+};
+use slint::{
+    CloseRequestResponse, Model, ModelRc, PhysicalPosition, PhysicalSize, SharedString, VecModel,
+};
 use std::cell::RefCell;
-use std::io;
+use std::collections::HashSet;
+use std::future::Future;
 use std::rc::Rc;
-use tokio::runtime::Runtime;
-
-#[derive(Debug, thiserror::Error)]
-enum WrapperError {
-    #[error(transparent)]
-    HibpError(#[from] hibp::HibpError),
-    #[error("Slint Error: {0}")]
-    SlintError(#[from] slint::PlatformError),
-    #[error("JSON parsing failed: {0}")]
-    JsonParseError(#[from] serde_json::Error),
-    #[error(transparent)]
-    IoError(#[from] io::Error),
+use std::time::Duration;
+use tokio::runtime::{self, Runtime};
+
+/// Formats a large count in a compact form (e.g. `13_240_000_000` -> `"13.24B"`).
+fn format_compact(count: u64) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=999_999 => format!("{:.2}K", count as f64 / 1e3),
+        1_000_000..=999_999_999 => format!("{:.2}M", count as f64 / 1e6),
+        _ => format!("{:.2}B", count as f64 / 1e9),
+    }
+}
+
+/// Runs a client request to completion, giving up with [`HibpError::Timeout`] once `deadline` has
+/// passed so a stalled request can't leave the window frozen.
+fn block_on_with_deadline<T>(
+    runtime: &Runtime,
+    deadline: Duration,
+    request: impl Future<Output = HibpResult<T>>,
+) -> HibpResult<T> {
+    runtime.block_on(async {
+        tokio::time::timeout(deadline, request)
+            .await
+            .unwrap_or(Err(HibpError::Timeout))
+    })
+}
+
+/// Converts a breach into the window's representation, arranging its data classes and leaving out
+/// hidden fields per `config` (the window skips empty fields).
+fn to_slint_breach(config: &Config, breach: &hibp::Breach) -> Breach {
+    let fields = config.visible_fields;
+    let data_classes = if fields.data_classes {
+        config.arrange_data_classes(&breach.data_classes)
+    } else {
+        Vec::new()
+    };
+    let high_sensitivity: Vec<bool> = data_classes
+        .iter()
+        .map(|class| hibp::data_class_sensitivity(class) == hibp::Sensitivity::High)
+        .collect();
+    let shown = |visible: bool, value: String| {
+        if visible {
+            SharedString::from(value)
+        } else {
+            SharedString::new()
+        }
+    };
+
+    Breach {
+        name: SharedString::from(&breach.name),
+        domain: shown(fields.domain, breach.domain.clone()),
+        pwn_count: if fields.pwn_count {
+            breach.pwn_count as i32
+        } else {
+            0
+        },
+        description: SharedString::from(&html_to_text(breach.description.as_str())),
+        breach_date: shown(
+            fields.date,
+            format_breach_date(
+                breach.breach_date,
+                config.date_style,
+                chrono::Local::now().date_naive(),
+            ),
+        ),
+        flags: shown(fields.flags, hibp::active_flags(breach).join(", ")),
+        data_classes: ModelRc::new(VecModel::from(
+            data_classes
+                .into_iter()
+                .map(SharedString::from)
+                .collect::<Vec<_>>(),
+        )),
+        high_sensitivity: ModelRc::new(VecModel::from(high_sensitivity)),
+    }
 }
 
-type WrapperResult<T> = std::result::Result<T, WrapperError>;
+/// Sorts the breaches found for an email in the configured order (see [`BreachOrder`]).
+fn sort_account_breaches(breaches: &mut [hibp::Breach], order: BreachOrder) {
+    match order {
+        BreachOrder::Severity => breaches.sort_by(|a, b| {
+            hibp::breach_severity(b)
+                .cmp(&hibp::breach_severity(a))
+                .then_with(|| b.breach_date.cmp(&a.breach_date))
+        }),
+        // Breaches that allow account takeover first
+        BreachOrder::Credentials => breaches.sort_by_key(|b| !hibp::exposes_credentials(b)),
+    }
+}
 
-fn remove_tags(input: &str) -> String {
-    let mut result = String::with_capacity(input.len());
-    let mut inside_tag = false;
+/// Explains a maintenance window, with the expected wait when HIBP gave one.
+fn maintenance_message(retry_after: Option<Duration>) -> String {
+    match retry_after {
+        Some(wait) if wait.as_secs() >= 60 => format!(
+            "HIBP is under planned maintenance\nPlease try again in about {} minutes",
+            wait.as_secs().div_ceil(60)
+        ),
+        Some(_) => "HIBP is under planned maintenance\nPlease try again in a minute".to_string(),
+        None => "HIBP is under planned maintenance\nPlease try again later".to_string(),
+    }
+}
+
+/// Shows why a query failed in the overlay, the same way for every query. `what` names what the
+/// query was getting, e.g. "this breach".
+fn show_query_error(ui: &MainWindow, state: &AppState, err: HibpError, what: &str) {
+    ui.set_result_state(ResultState::Error);
+    match err {
+        HibpError::Timeout => {
+            ui.set_overlay_message(SharedString::from(
+                "The request timed out\nPlease check your connection and try again",
+            ));
+            ui.set_overlay_title(SharedString::from("Error!"));
+        }
+        HibpError::BannedUserAgent => {
+            ui.set_overlay_message(SharedString::from(format!("Error: {}", err)));
+            ui.set_overlay_title(SharedString::from("Error!"));
+        }
+        HibpError::RateLimit { .. } => state.show_notice(
+            ui,
+            "Slow down",
+            "Too many requests in a short time\nPlease wait a moment and try again".to_string(),
+        ),
+        HibpError::Maintenance { retry_after } => {
+            ui.set_overlay_message(SharedString::from(maintenance_message(retry_after)));
+            ui.set_overlay_title(SharedString::from("Maintenance"));
+        }
+        err => {
+            eprintln!("Couldn't get {what}: {err}");
+            ui.set_overlay_message(SharedString::from(format!("Couldn't get {what}\n{err}")));
+            ui.set_overlay_title(SharedString::from("Error!"));
+        }
+    }
+}
 
-    for ch in input.chars() {
-        match ch {
-            '<' => inside_tag = true,
-            '>' => inside_tag = false,
-            _ => {
-                if !inside_tag {
-                    result.push(ch);
+/// Result state of a successful query, depending on whether it found anything.
+fn success_state(found: bool) -> ResultState {
+    if found {
+        ResultState::HasResults
+    } else {
+        ResultState::Clean
+    }
+}
+
+/// A query run from the window, with the inputs needed to run it again.
+///
+/// Secrets aren't kept: a password is remembered by its SHA1 hash, and email queries are re-run
+/// with the API key currently entered in the window.
+#[derive(Debug, Clone)]
+enum LastQuery {
+    EmailBreaches(SharedString),
+    EmailPastes(SharedString),
+    /// Uppercase SHA1 hex of the password.
+    PasswordHash(SharedString),
+    Breach(SharedString),
+    LatestBreach,
+    AllBreaches,
+}
+
+/// State shared by the window's handlers.
+struct AppState {
+    last_query: RefCell<Option<LastQuery>>,
+    /// Display settings (see [`Config::max_display`] and the data class settings).
+    config: Config,
+    /// Full result of the last breach list query.
+    breaches: RefCell<Vec<hibp::Breach>>,
+    /// The part of `breaches` rendered in the window.
+    shown_breaches: Rc<VecModel<Breach>>,
+    /// Result of the last single breach query.
+    breach: RefCell<Option<hibp::Breach>>,
+    /// Result of the last paste query.
+    pastes: RefCell<Vec<hibp::Paste>>,
+    /// Email the last successful account query was for, `None` after a query that isn't about
+    /// an account replaced the breach list.
+    account: RefCell<Option<String>>,
+    /// Opened on first use, then kept: on X11 the copied text is only available while the
+    /// clipboard owning it is alive.
+    clipboard: RefCell<Option<arboard::Clipboard>>,
+    /// Domains of throwaway email services, warned about before account lookups.
+    disposable_domains: HashSet<String>,
+}
+
+impl AppState {
+    fn new(config: &Config) -> Self {
+        Self {
+            last_query: RefCell::new(None),
+            config: config.clone(),
+            breaches: RefCell::new(Vec::new()),
+            shown_breaches: Rc::new(VecModel::default()),
+            breach: RefCell::new(None),
+            pastes: RefCell::new(Vec::new()),
+            account: RefCell::new(None),
+            clipboard: RefCell::new(None),
+            disposable_domains: config::disposable_domains(),
+        }
+    }
+
+    /// Shows an informational notice in the overlay, closing it after the configured delay (see
+    /// [`Config::notice_dismiss_secs`]) unless it was replaced or closed in the meantime.
+    fn show_notice(&self, ui: &MainWindow, title: &str, message: String) {
+        let message = SharedString::from(message);
+        ui.set_overlay_message(message.clone());
+        ui.set_overlay_title(SharedString::from(title));
+
+        if self.config.notice_dismiss_secs == 0 {
+            return;
+        }
+        let ui_weak = ui.as_weak();
+        let title = SharedString::from(title);
+        slint::Timer::single_shot(
+            Duration::from_secs(self.config.notice_dismiss_secs),
+            move || {
+                if let Some(ui) = ui_weak.upgrade() {
+                    if ui.get_overlay_title() == title && ui.get_overlay_message() == message {
+                        ui.set_overlay_title(SharedString::new());
+                        ui.set_overlay_message(SharedString::new());
+                    }
                 }
+            },
+        );
+    }
+
+    /// Shows a notice if `email` belongs to a throwaway email service. The lookup still runs; an
+    /// error it runs into replaces the notice.
+    fn warn_if_disposable(&self, ui: &MainWindow, email: &str) {
+        if is_disposable_email(email, &self.disposable_domains) {
+            self.show_notice(
+                ui,
+                "Heads up",
+                "This looks like a throwaway email address\nIts results may not tell much about a real account".to_string(),
+            );
+        }
+    }
+
+    /// Returns `email` as it may be shown, exported or logged: masked with [`redact_email`] if
+    /// [`Config::redact_emails`] is set.
+    fn shown_email(&self, email: &str) -> String {
+        if self.config.redact_emails {
+            redact_email(email)
+        } else {
+            email.trim().to_string()
+        }
+    }
+
+    /// Sets the email the displayed results are for (`None` for results not about an account),
+    /// labelling the result views with it.
+    fn set_account(&self, ui: &MainWindow, email: Option<&str>) {
+        let label = email
+            .map(|email| format!("Results for {}", self.shown_email(email)))
+            .unwrap_or_default();
+        ui.set_account_label(SharedString::from(label));
+        *self.account.borrow_mut() = email.map(str::to_string);
+    }
+
+    /// Remembers `query` as the last one run, enabling the refresh action.
+    fn record(&self, ui: &MainWindow, query: LastQuery) {
+        *self.last_query.borrow_mut() = Some(query);
+        ui.set_has_last_query(true);
+    }
+
+    /// Runs the last query again with the same inputs, through the same handler as the original.
+    /// Does nothing if no query was run yet.
+    fn rerun_last(&self, ui: &MainWindow) {
+        // Cloned so the handler can record the query again
+        let Some(query) = self.last_query.borrow().clone() else {
+            return;
+        };
+
+        match query {
+            LastQuery::EmailBreaches(email) => ui.invoke_submit_e_breach(email, ui.get_key()),
+            LastQuery::EmailPastes(email) => ui.invoke_submit_e_pastes(email, ui.get_key()),
+            LastQuery::PasswordHash(sha1) => ui.invoke_submit_password_hash(sha1),
+            LastQuery::Breach(name) => ui.invoke_submit_breach(name),
+            LastQuery::LatestBreach => ui.invoke_get_latest(),
+            LastQuery::AllBreaches => ui.invoke_get_all(),
+        }
+    }
+
+    /// Replaces the breach list, rendering only its first `max_display` breaches.
+    /// The full list is kept for [`AppState::show_more_breaches`].
+    fn set_breaches(&self, ui: &MainWindow, breaches: Vec<hibp::Breach>) {
+        let shown = self.next_page_end(0, breaches.len());
+        self.shown_breaches.set_vec(
+            breaches[..shown]
+                .iter()
+                .map(|b| to_slint_breach(&self.config, b))
+                .collect::<Vec<_>>(),
+        );
+        ui.set_breaches_total(breaches.len() as i32);
+        *self.breaches.borrow_mut() = breaches;
+    }
+
+    /// Renders the next `max_display` breaches of the list.
+    fn show_more_breaches(&self) {
+        let breaches = self.breaches.borrow();
+        let start = self.shown_breaches.row_count();
+        let end = self.next_page_end(start, breaches.len());
+        self.shown_breaches.extend(
+            breaches[start..end]
+                .iter()
+                .map(|b| to_slint_breach(&self.config, b)),
+        );
+    }
+
+    /// End of the page of breaches starting at `start`, out of `len` (every breach if the cap is 0).
+    fn next_page_end(&self, start: usize, len: usize) -> usize {
+        match self.config.max_display {
+            0 => len,
+            max => start.saturating_add(max).min(len),
+        }
+    }
+
+    /// Copies the whole breach list (not only the rendered part) to the clipboard as JSON. The
+    /// breaches of an account are wrapped with the email they're for (see
+    /// [`AppState::account_json`]).
+    fn copy_breaches_json(&self, ui: &MainWindow) {
+        let breaches = self.breaches.borrow();
+        let json: Vec<_> = breaches.iter().map(|b| self.breach_json(b)).collect();
+        let what = format!("{} breaches", breaches.len());
+        if self.account.borrow().is_some() {
+            self.copy_json(ui, &what, &self.account_json("Breaches", json));
+        } else {
+            self.copy_json(ui, &what, &json);
+        }
+    }
+
+    /// Wraps an account's results as `{"Account": email, <key>: results}`, with the email as
+    /// [`AppState::shown_email`] gives it.
+    fn account_json(&self, key: &str, results: impl Serialize) -> serde_json::Value {
+        let account = self.account.borrow();
+        serde_json::json!({
+            "Account": account.as_deref().map(|email| self.shown_email(email)),
+            key: results,
+        })
+    }
+
+    /// Returns a breach as HIBP's JSON, without the fields hidden in the settings.
+    fn breach_json(&self, breach: &hibp::Breach) -> serde_json::Value {
+        let mut json = serde_json::to_value(breach).unwrap_or_default();
+        if let Some(object) = json.as_object_mut() {
+            for key in self.config.visible_fields.hidden_json_keys() {
+                object.remove(key);
+            }
+        }
+        json
+    }
+
+    /// Copies the breach named `name` to the clipboard as JSON, whether it was opened from the
+    /// breach list or looked up on its own.
+    fn copy_breach_json(&self, ui: &MainWindow, name: &str) {
+        let single = self.breach.borrow();
+        let breaches = self.breaches.borrow();
+        let breach = single
+            .iter()
+            .chain(breaches.iter())
+            .find(|b| b.name == name);
+
+        if let Some(breach) = breach {
+            self.copy_json(ui, &breach.title, &self.breach_json(breach));
+        }
+    }
+
+    /// Copies the paste list to the clipboard as JSON, wrapped with the email it's for (see
+    /// [`AppState::account_json`]).
+    fn copy_pastes_json(&self, ui: &MainWindow) {
+        let pastes = self.pastes.borrow();
+        let json = self.account_json("Pastes", &*pastes);
+        self.copy_json(ui, &format!("{} pastes", pastes.len()), &json);
+    }
+
+    /// Places `value` on the clipboard as pretty-printed JSON (the same form HIBP returns), and
+    /// reports the outcome in the overlay.
+    fn copy_json<T: Serialize + ?Sized>(&self, ui: &MainWindow, what: &str, value: &T) {
+        let result = serde_json::to_string_pretty(value)
+            .map_err(EmiconError::from)
+            .and_then(|json| self.set_clipboard(json));
+
+        match result {
+            Ok(size) => self.show_notice(
+                ui,
+                "Copied!",
+                format!(
+                    "{what} copied to the clipboard as JSON ({} KB)",
+                    format_count(size.div_ceil(1024) as u64)
+                ),
+            ),
+            Err(e) => {
+                ui.set_overlay_message(SharedString::from(format!("Error: {}", e)));
+                ui.set_overlay_title(SharedString::from("Error!"));
             }
         }
     }
 
-    result
+    /// Places `text` on the clipboard, returning its size in bytes.
+    fn set_clipboard(&self, text: String) -> EmiconResult<usize> {
+        let mut clipboard = self.clipboard.borrow_mut();
+        if clipboard.is_none() {
+            *clipboard = Some(arboard::Clipboard::new()?);
+        }
+
+        let size = text.len();
+        if let Some(clipboard) = clipboard.as_mut() {
+            clipboard.set_text(text)?;
+        }
+        Ok(size)
+    }
 }
 
-fn main() -> WrapperResult<()> {
+fn main() -> EmiconResult<()> {
     let ui = MainWindow::new()?;
 
     // Wrap client and runtime in Rc<RefCell<>> for shared mutable access across closures
@@ -52,13 +438,63 @@ fn main() -> WrapperResult<()> {
         "HibpWrapper".to_string(),
         20,
     )?));
-    let runtime = Rc::new(Runtime::new()?);
+    client
+        .borrow_mut()
+        .change_common_passwords(config::common_passwords());
+    // Repeated clicks on the password check button share one range request
+    client
+        .borrow_mut()
+        .change_range_dedup(Some(Duration::from_millis(500)));
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("Failed to load settings, using defaults: {e}");
+        Config::default()
+    });
+    let mut runtime = runtime::Builder::new_multi_thread();
+    if config.worker_threads > 0 {
+        runtime.worker_threads(config.worker_threads);
+    }
+    let runtime = Rc::new(runtime.enable_all().build()?);
+    let state = Rc::new(AppState::new(&config));
+    ui.set_breaches(ModelRc::from(state.shown_breaches.clone()));
+    if let Some(geometry) = config.window {
+        let geometry = geometry.clamped();
+        ui.window()
+            .set_size(PhysicalSize::new(geometry.width, geometry.height));
+        ui.window()
+            .set_position(PhysicalPosition::new(geometry.x, geometry.y));
+    }
+
+    // Remember the window geometry on close. The settings file is reloaded first so edits made
+    // to it while the application was running are kept.
+    {
+        let ui_weak = ui.as_weak();
+
+        ui.window().on_close_requested(move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                let (size, position) = (ui.window().size(), ui.window().position());
+                let result = Config::load().and_then(|mut config| {
+                    config.window = Some(WindowState {
+                        width: size.width,
+                        height: size.height,
+                        x: position.x,
+                        y: position.y,
+                    });
+                    config.save()
+                });
+                if let Err(e) = result {
+                    eprintln!("Failed to save the window geometry: {e}");
+                }
+            }
+            CloseRequestResponse::HideWindow
+        });
+    }
 
     // Handler for email breach lookup
     {
         let ui_weak = ui.as_weak();
         let client_clone = Rc::clone(&client);
         let runtime_clone = Rc::clone(&runtime);
+        let state_clone = Rc::clone(&state);
 
         ui.on_submit_e_breach(move |email, api_key| {
             let ui = match ui_weak.upgrade() {
@@ -66,6 +502,9 @@ fn main() -> WrapperResult<()> {
                 None => return,
             };
 
+            state_clone.record(&ui, LastQuery::EmailBreaches(email.clone()));
+            state_clone.warn_if_disposable(&ui, &email);
+
             let email_str = email.as_str();
             let key_str = api_key.as_str();
 
@@ -79,41 +518,27 @@ fn main() -> WrapperResult<()> {
             // Fetch breaches
             let fut = {
                 let client_ref = client_clone.borrow();
-                runtime_clone.block_on(async { client_ref.get_account_breaches(email_str).await })
+                block_on_with_deadline(
+                    &runtime_clone,
+                    client_ref.deadline(),
+                    client_ref.get_account_breaches(email_str),
+                )
             };
 
             match fut {
-                Ok(breaches) => {
-                    let slint_breaches: Vec<Breach> = breaches
-                        .iter()
-                        .map(|b| Breach {
-                            name: SharedString::from(&b.name),
-                            domain: SharedString::from(&b.domain),
-                            pwn_count: b.pwn_count as i32,
-                            description: SharedString::from(&remove_tags(b.description.as_str())),
-                            breach_date: SharedString::from(&b.breach_date.to_string()),
-                            data_classes: ModelRc::new(VecModel::from(
-                                b.data_classes
-                                    .iter()
-                                    .map(|dc| SharedString::from(dc))
-                                    .collect::<Vec<_>>(),
-                            )),
-                        })
-                        .collect();
-
-                    ui.set_successful(true);
-                    ui.set_breaches(ModelRc::new(VecModel::from(slint_breaches)));
+                Ok(mut breaches) => {
+                    sort_account_breaches(&mut breaches, state_clone.config.account_breach_order);
+                    ui.set_result_state(success_state(!breaches.is_empty()));
+                    state_clone.set_account(&ui, Some(email_str));
+                    state_clone.set_breaches(&ui, breaches);
                 }
                 Err(err) => match err {
                     HibpError::NotFound => {
-                        ui.set_successful(true);
-                        ui.set_breaches(ModelRc::new(VecModel::from(Vec::new())))
-                    }
-                    _ => {
-                        ui.set_successful(false);
-                        ui.set_overlay_message(SharedString::from(format!("HIBP is now dealing with a service issue\nBoth email breach and email pastes endpoints are down, but should comeback shortly")));
-                        ui.set_overlay_title(SharedString::from("Error!"));
+                        ui.set_result_state(ResultState::Clean);
+                        state_clone.set_account(&ui, Some(email_str));
+                        state_clone.set_breaches(&ui, Vec::new())
                     }
+                    err => show_query_error(&ui, &state_clone, err, "this email's breaches"),
                 },
             }
         });
@@ -124,6 +549,7 @@ fn main() -> WrapperResult<()> {
         let ui_weak = ui.as_weak();
         let client_clone = Rc::clone(&client);
         let runtime_clone = Rc::clone(&runtime);
+        let state_clone = Rc::clone(&state);
 
         ui.on_submit_e_pastes(move |email, api_key| {
             let ui = match ui_weak.upgrade() {
@@ -131,6 +557,9 @@ fn main() -> WrapperResult<()> {
                 None => return,
             };
 
+            state_clone.record(&ui, LastQuery::EmailPastes(email.clone()));
+            state_clone.warn_if_disposable(&ui, &email);
+
             let email_str = email.as_str();
             let key_str = api_key.as_str();
 
@@ -143,7 +572,11 @@ fn main() -> WrapperResult<()> {
             // Fetch pastes
             let fut = {
                 let client_ref = client_clone.borrow();
-                runtime_clone.block_on(async { client_ref.get_account_pastes(email_str).await })
+                block_on_with_deadline(
+                    &runtime_clone,
+                    client_ref.deadline(),
+                    client_ref.get_account_pastes(email_str),
+                )
             };
 
             match fut {
@@ -161,53 +594,78 @@ fn main() -> WrapperResult<()> {
                         })
                         .collect();
 
-                    ui.set_successful(true);
+                    let summary = paste_summary(&pastes);
+                    ui.set_paste_summary(SharedString::from(format!(
+                        "Appeared in {} pastes across {} sources, ~{} emails exposed",
+                        summary.paste_count,
+                        summary.source_count,
+                        format_count(summary.email_count)
+                    )));
+
+                    ui.set_result_state(success_state(!pastes.is_empty()));
+                    state_clone.set_account(&ui, Some(email_str));
                     ui.set_pastes(ModelRc::new(VecModel::from(slint_pastes)));
+                    *state_clone.pastes.borrow_mut() = pastes;
                 }
                 Err(err) => match err {
                     HibpError::NotFound => {
-                        ui.set_successful(true);
-                        ui.set_pastes(ModelRc::new(VecModel::from(Vec::new())))
-                    }
-                    _ => {
-                        ui.set_successful(false);
-                        ui.set_overlay_message(SharedString::from(format!("HIBP is now dealing with a service issue\nBoth email breach and email pastes endpoints are down, but should comeback shortly")));
-                        ui.set_overlay_title(SharedString::from("Error!"));
+                        ui.set_result_state(ResultState::Clean);
+                        state_clone.set_account(&ui, Some(email_str));
+                        ui.set_paste_summary(SharedString::from(""));
+                        ui.set_pastes(ModelRc::new(VecModel::from(Vec::new())));
+                        state_clone.pastes.borrow_mut().clear();
                     }
+                    err => show_query_error(&ui, &state_clone, err, "this email's pastes"),
                 },
             }
         });
     }
 
-    // Handler for password check
+    // Handler for password check, hashing the password so only its hash is passed on
+    {
+        let ui_weak = ui.as_weak();
+
+        ui.on_submit_password(move |password| {
+            let ui = match ui_weak.upgrade() {
+                Some(ui) => ui,
+                None => return,
+            };
+
+            let sha1_hex = hex::encode_upper(Sha1::digest(password.as_bytes()));
+            ui.invoke_submit_password_hash(SharedString::from(sha1_hex));
+        });
+    }
+
+    // Handler for password hash check
     {
         let ui_weak = ui.as_weak();
         let client_clone = Rc::clone(&client);
         let runtime_clone = Rc::clone(&runtime);
+        let state_clone = Rc::clone(&state);
 
-        ui.on_submit_password(move |password| {
+        ui.on_submit_password_hash(move |sha1_hex| {
             let ui = match ui_weak.upgrade() {
                 Some(ui) => ui,
                 None => return,
             };
 
-            let password_str = password.as_str();
+            state_clone.record(&ui, LastQuery::PasswordHash(sha1_hex.clone()));
 
             let fut = {
                 let client_ref = client_clone.borrow();
-                runtime_clone.block_on(async { client_ref.check_password(password_str).await })
+                block_on_with_deadline(
+                    &runtime_clone,
+                    client_ref.deadline(),
+                    client_ref.check_password_hash(&sha1_hex),
+                )
             };
 
             match fut {
                 Ok(count) => {
-                    ui.set_successful(true);
+                    ui.set_result_state(success_state(count > 0));
                     ui.set_password_count(count as i32);
                 }
-                Err(e) => {
-                    ui.set_successful(false);
-                    ui.set_overlay_message(SharedString::from(format!("Error: {}", e)));
-                    ui.set_overlay_title(SharedString::from("Error!"));
-                }
+                Err(e) => show_query_error(&ui, &state_clone, e, "the password's breach count"),
             }
         });
     }
@@ -217,6 +675,7 @@ fn main() -> WrapperResult<()> {
         let ui_weak = ui.as_weak();
         let client_clone = Rc::clone(&client);
         let runtime_clone = Rc::clone(&runtime);
+        let state_clone = Rc::clone(&state);
 
         ui.on_submit_breach(move |breach_name| {
             let ui = match ui_weak.upgrade() {
@@ -224,41 +683,27 @@ fn main() -> WrapperResult<()> {
                 None => return,
             };
 
+            state_clone.record(&ui, LastQuery::Breach(breach_name.clone()));
+
             let name_str = breach_name.as_str();
 
             // Fetch breach data
             let fut = {
                 let client_ref = client_clone.borrow();
-                runtime_clone.block_on(async { client_ref.get_breach(name_str).await })
+                block_on_with_deadline(
+                    &runtime_clone,
+                    client_ref.deadline(),
+                    client_ref.get_breach(name_str),
+                )
             };
 
             match fut {
                 Ok(breach_data) => {
-                    let breach = Breach {
-                        name: SharedString::from(&breach_data.name),
-                        domain: SharedString::from(&breach_data.domain),
-                        pwn_count: breach_data.pwn_count as i32,
-                        description: SharedString::from(&remove_tags(
-                            breach_data.description.as_str(),
-                        )),
-                        breach_date: SharedString::from(&breach_data.breach_date.to_string()),
-                        data_classes: ModelRc::new(VecModel::from(
-                            breach_data
-                                .data_classes
-                                .iter()
-                                .map(|dc| SharedString::from(dc))
-                                .collect::<Vec<_>>(),
-                        )),
-                    };
-
-                    ui.set_successful(true);
-                    ui.set_breach(breach);
-                }
-                Err(e) => {
-                    ui.set_successful(false);
-                    ui.set_overlay_message(SharedString::from(format!("Error: {}", e)));
-                    ui.set_overlay_title(SharedString::from("Error!"));
+                    ui.set_result_state(ResultState::HasResults);
+                    ui.set_breach(to_slint_breach(&state_clone.config, &breach_data));
+                    *state_clone.breach.borrow_mut() = Some(breach_data);
                 }
+                Err(e) => show_query_error(&ui, &state_clone, e, "this breach"),
             }
         });
     }
@@ -268,6 +713,7 @@ fn main() -> WrapperResult<()> {
         let ui_weak = ui.as_weak();
         let client_clone = Rc::clone(&client);
         let runtime_clone = Rc::clone(&runtime);
+        let state_clone = Rc::clone(&state);
 
         ui.on_get_latest(move || {
             let ui = match ui_weak.upgrade() {
@@ -275,39 +721,25 @@ fn main() -> WrapperResult<()> {
                 None => return,
             };
 
+            state_clone.record(&ui, LastQuery::LatestBreach);
+
             // Fetch breach data
             let fut = {
                 let client_ref = client_clone.borrow();
-                runtime_clone.block_on(async { client_ref.get_latest_breach().await })
+                block_on_with_deadline(
+                    &runtime_clone,
+                    client_ref.deadline(),
+                    client_ref.get_latest_breach(),
+                )
             };
 
             match fut {
                 Ok(breach_data) => {
-                    let breach = Breach {
-                        name: SharedString::from(&breach_data.name),
-                        domain: SharedString::from(&breach_data.domain),
-                        pwn_count: breach_data.pwn_count as i32,
-                        description: SharedString::from(&remove_tags(
-                            breach_data.description.as_str(),
-                        )),
-                        breach_date: SharedString::from(&breach_data.breach_date.to_string()),
-                        data_classes: ModelRc::new(VecModel::from(
-                            breach_data
-                                .data_classes
-                                .iter()
-                                .map(|dc| SharedString::from(dc))
-                                .collect::<Vec<_>>(),
-                        )),
-                    };
-
-                    ui.set_successful(true);
-                    ui.set_breach(breach);
-                }
-                Err(e) => {
-                    ui.set_successful(false);
-                    ui.set_overlay_message(SharedString::from(format!("Error: {}", e)));
-                    ui.set_overlay_title(SharedString::from("Error!"));
+                    ui.set_result_state(ResultState::HasResults);
+                    ui.set_breach(to_slint_breach(&state_clone.config, &breach_data));
+                    *state_clone.breach.borrow_mut() = Some(breach_data);
                 }
+                Err(e) => show_query_error(&ui, &state_clone, e, "the latest breach"),
             }
         });
     }
@@ -317,6 +749,7 @@ fn main() -> WrapperResult<()> {
         let ui_weak = ui.as_weak();
         let client_clone = Rc::clone(&client);
         let runtime_clone = Rc::clone(&runtime);
+        let state_clone = Rc::clone(&state);
 
         ui.on_get_all(move || {
             let ui = match ui_weak.upgrade() {
@@ -324,39 +757,80 @@ fn main() -> WrapperResult<()> {
                 None => return,
             };
 
+            state_clone.record(&ui, LastQuery::AllBreaches);
+
             // Fetch breach data
             let fut = {
                 let client_ref = client_clone.borrow();
-                runtime_clone.block_on(async { client_ref.get_all_breaches().await })
+                block_on_with_deadline(
+                    &runtime_clone,
+                    client_ref.deadline(),
+                    client_ref.get_all_breaches(),
+                )
             };
 
             match fut {
                 Ok(breaches) => {
-                    let slint_breaches: Vec<Breach> = breaches
-                        .iter()
-                        .map(|b| Breach {
-                            name: SharedString::from(&b.name),
-                            domain: SharedString::from(&b.domain),
-                            pwn_count: b.pwn_count as i32,
-                            description: SharedString::from(&remove_tags(b.description.as_str())),
-                            breach_date: SharedString::from(&b.breach_date.to_string()),
-                            data_classes: ModelRc::new(VecModel::from(
-                                b.data_classes
-                                    .iter()
-                                    .map(|dc| SharedString::from(dc))
-                                    .collect::<Vec<_>>(),
-                            )),
-                        })
-                        .collect();
+                    let stats = GlobalStats::from_breaches(&breaches);
+                    ui.set_total_breaches(SharedString::from(stats.breach_count.to_string()));
+                    ui.set_pwned_accounts(SharedString::from(format_compact(stats.pwn_count)));
 
-                    ui.set_successful(true);
-                    ui.set_breaches(ModelRc::new(VecModel::from(slint_breaches)));
-                }
-                Err(e) => {
-                    ui.set_successful(false);
-                    ui.set_overlay_message(SharedString::from(format!("Error: {}", e)));
-                    ui.set_overlay_title(SharedString::from("Error!"));
+                    ui.set_result_state(success_state(!breaches.is_empty()));
+                    state_clone.set_account(&ui, None);
+                    state_clone.set_breaches(&ui, breaches);
                 }
+                Err(e) => show_query_error(&ui, &state_clone, e, "the breach list"),
+            }
+        });
+    }
+
+    // Handler for the refresh action
+    {
+        let ui_weak = ui.as_weak();
+        let state_clone = Rc::clone(&state);
+
+        ui.on_rerun_last(move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                state_clone.rerun_last(&ui);
+            }
+        });
+    }
+
+    // Handler for rendering more of the breach list
+    {
+        let state_clone = Rc::clone(&state);
+
+        ui.on_show_more_breaches(move || state_clone.show_more_breaches());
+    }
+
+    // Handlers for copying the displayed results as JSON
+    {
+        let ui_weak = ui.as_weak();
+        let state_clone = Rc::clone(&state);
+
+        ui.on_copy_breaches_json(move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                state_clone.copy_breaches_json(&ui);
+            }
+        });
+    }
+    {
+        let ui_weak = ui.as_weak();
+        let state_clone = Rc::clone(&state);
+
+        ui.on_copy_breach_json(move |name| {
+            if let Some(ui) = ui_weak.upgrade() {
+                state_clone.copy_breach_json(&ui, name.as_str());
+            }
+        });
+    }
+    {
+        let ui_weak = ui.as_weak();
+        let state_clone = Rc::clone(&state);
+
+        ui.on_copy_pastes_json(move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                state_clone.copy_pastes_json(&ui);
             }
         });
     }
@@ -34,7 +34,15 @@
 // ║
 // ╚══════════════════════════════════════════════════════════════════════════════════════════════╝
 
+#![allow(dead_code)]
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::shared::DEFAULT_MAX_RETRY_AFTER;
 use tokio::sync::{Mutex, Notify};
 use tokio::time::{sleep_until, Duration, Instant};
 
@@ -62,6 +70,53 @@ pub struct TokenBucket {
     inner: Arc<Mutex<TokenBucketInner>>,
     /// Notifier for waking up waiting consumers when backoff periods end
     notify: Arc<Notify>,
+    /// Longest wait [`TokenBucket::parse_retry_after`] returns
+    max_retry_after: Duration,
+}
+
+/// A point-in-time view of a [`TokenBucket`]'s configuration and state.
+///
+/// Returned by [`TokenBucket::snapshot`], mainly for troubleshooting why requests are delayed.
+#[derive(Debug, Clone, Serialize)]
+pub struct BucketSnapshot {
+    /// Maximum number of tokens the bucket can hold
+    pub capacity: u32,
+    /// Rate at which tokens are added per second
+    pub refill_rate: f64,
+    /// Number of tokens available when the snapshot was taken
+    pub tokens: u32,
+    /// Time left in the current backoff period, if any
+    pub backoff_remaining: Option<Duration>,
+}
+
+/// A point-in-time summary of whether a [`TokenBucket`] is holding requests back.
+///
+/// Returned by [`TokenBucket::health`], e.g. for a diagnostics panel. All fields are read under
+/// the same lock, so they agree with each other.
+#[derive(Debug, Clone, Serialize)]
+pub struct BucketHealth {
+    /// Whether [`TokenBucket::consume`] would have to wait right now
+    pub throttling: bool,
+    /// Number of tokens available
+    pub tokens: u32,
+    /// Time left until a token can be taken, `None` if one can be taken right now (see
+    /// [`TokenBucket::time_until_next_token`])
+    pub time_to_next_token: Option<Duration>,
+    /// Whether a backoff period is active
+    pub in_backoff: bool,
+}
+
+/// The state [`TokenBucket::save_state`] writes, with wall-clock times so it survives a restart.
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedState {
+    capacity: u32,
+    refill_rate: f64,
+    /// Available tokens, including the fractional remainder
+    tokens: f64,
+    /// When the state was saved, in milliseconds since the Unix epoch
+    saved_at_ms: u64,
+    /// End of the backoff period active when saved, in milliseconds since the Unix epoch
+    backoff_until_ms: Option<u64>,
 }
 
 /// Internal state of the token bucket.
@@ -81,8 +136,13 @@ struct TokenBucketInner {
     /// Optional pause period during which no tokens can be consumed
     /// (used for implementing backoff after rate limit errors)
     pause_until: Option<Instant>,
+    /// When each token consumed within the last minute was taken, oldest first
+    consumed_at: VecDeque<Instant>,
 }
 
+/// Length of the sliding window over which consumed tokens are counted.
+const CONSUMPTION_WINDOW: Duration = Duration::from_secs(60);
+
 impl TokenBucket {
     /// Creates a new token bucket with the specified capacity and refill rate.
     ///
@@ -109,11 +169,32 @@ impl TokenBucket {
                 last_refill: Instant::now(),
                 remainder: 0.0,
                 pause_until: None,
+                consumed_at: VecDeque::new(),
             })),
             notify: Arc::new(Notify::new()),
+            max_retry_after: DEFAULT_MAX_RETRY_AFTER,
         }
     }
 
+    /// Sets the longest wait [`TokenBucket::parse_retry_after`] returns (1 hour by default).
+    ///
+    /// Retry-After values implying a longer wait are most likely a skewed local clock (for HTTP
+    /// dates) or a malformed header, so they are capped rather than stalling requests for days.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - The longest wait to accept
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let bucket = TokenBucket::new(10, 2.0).with_max_retry_after(Duration::from_secs(600));
+    /// ```
+    pub fn with_max_retry_after(mut self, max: Duration) -> Self {
+        self.max_retry_after = max;
+        self
+    }
+
     /// Initiates a backoff period during which no tokens can be consumed.
     ///
     /// This method is useful when handling rate limit errors from APIs. During the
@@ -159,6 +240,11 @@ impl TokenBucket {
     /// - A number of seconds (e.g., "120")
     /// - An HTTP date (e.g., "Wed, 21 Oct 2015 07:28:00 GMT")
     ///
+    /// HTTP dates are compared against the local clock, so a clock running behind makes the wait
+    /// look longer than intended. Waits above the cap set with
+    /// [`TokenBucket::with_max_retry_after`] are logged and capped; dates in the past mean no wait.
+    /// See [`crate::shared::parse_retry_after`], which does the parsing.
+    ///
     /// # Arguments
     ///
     /// * `value` - The value from the Retry-After header
@@ -182,46 +268,31 @@ impl TokenBucket {
     /// }
     /// ```
     pub fn parse_retry_after(&self, value: &str) -> Duration {
-        // Try parsing as seconds first
-        if let Ok(secs) = value.trim().parse::<u64>() {
-            return Duration::from_secs(secs);
-        }
-
-        // Try parsing as HTTP date
-        if let Ok(date) = httpdate::parse_http_date(value.trim()) {
-            let now = std::time::SystemTime::now();
-            if let Ok(diff) = date.duration_since(now) {
-                return diff;
-            } else {
-                // If the date is in the past, don't wait
-                return Duration::from_secs(0);
-            }
-        };
-
-        Duration::from_secs(30)
+        crate::shared::parse_retry_after(value, self.max_retry_after)
+            .unwrap_or(Duration::from_secs(30))
     }
 
-    /// Attempts to consume a token without blocking.
-    ///
-    /// This method will immediately return whether a token was successfully consumed.
-    /// It will not wait if no tokens are available or if the bucket is in a backoff period.
-    ///
-    /// # Returns
-    ///
-    /// * `true` - A token was successfully consumed
-    /// * `false` - No token was available (bucket empty or in backoff)
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// if bucket.try_consume().await {
-    ///     // Proceed with rate-limited operation
-    ///     make_api_call().await;
-    /// } else {
-    ///     // Handle rate limit (maybe try again later)
-    ///     println!("Rate limited, try again later");
-    /// }
-    /// ```
+    // / Attempts to consume a token without blocking.
+    // /
+    // / This method will immediately return whether a token was successfully consumed.
+    // / It will not wait if no tokens are available or if the bucket is in a backoff period.
+    // /
+    // / # Returns
+    // /
+    // / * `true` - A token was successfully consumed
+    // / * `false` - No token was available (bucket empty or in backoff)
+    // /
+    // / # Example
+    // /
+    // / ```rust
+    // / if bucket.try_consume().await {
+    // /     // Proceed with rate-limited operation
+    // /     make_api_call().await;
+    // / } else {
+    // /     // Handle rate limit (maybe try again later)
+    // /     println!("Rate limited, try again later");
+    // / }
+    // / ```
     // pub async fn try_consume(&self) -> bool {
     //     {
     //         let mut inner = self.inner.lock().await;
@@ -253,6 +324,15 @@ impl TokenBucket {
     /// This method will block until a token can be successfully consumed. It respects
     /// backoff periods and will wait for them to expire before attempting to consume tokens.
     ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe: dropping the future (e.g. when a request is cancelled, or in
+    /// the losing branch of `tokio::select!`) never loses or reserves a token. A token is only
+    /// taken while the internal lock is held, and the future completes in that same step without
+    /// awaiting again, so a cancelled call either took no token or already returned. While
+    /// waiting, the future holds no lock and no place in a queue, so dropping it can't stall the
+    /// other consumers.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -264,6 +344,12 @@ impl TokenBucket {
     /// ```
     pub async fn consume(&self) {
         loop {
+            // Register for notifications before checking the state, so one sent between the
+            // check and the wait below isn't missed
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
             let (maybe_sleep_until, consumed) = {
                 let mut inner = self.inner.lock().await;
 
@@ -278,6 +364,7 @@ impl TokenBucket {
                         inner.refill();
                         if inner.tokens > 0 {
                             inner.tokens -= 1;
+                            inner.record_consumption();
                             (None, true)
                         } else {
                             // No tokens available, wait for next refill
@@ -289,6 +376,7 @@ impl TokenBucket {
                     inner.refill();
                     if inner.tokens > 0 {
                         inner.tokens -= 1;
+                        inner.record_consumption();
                         (None, true)
                     } else {
                         // Calculate when the next token will be available
@@ -307,12 +395,225 @@ impl TokenBucket {
             if let Some(until) = maybe_sleep_until {
                 tokio::select! {
                     _ = sleep_until(until) => {}, // Time-based wakeup
-                    _ = self.notify.notified() => {}, // State change notification
+                    _ = notified => {}, // State change notification
                 }
             }
         }
     }
 
+    /// Returns a snapshot of the bucket's configuration and current state.
+    ///
+    /// Tokens are refilled before the snapshot is taken, so the reported count is current.
+    /// An expired backoff period is reported as `None`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let snapshot = bucket.snapshot().await;
+    /// println!("{snapshot:?}");
+    /// ```
+    pub async fn snapshot(&self) -> BucketSnapshot {
+        let mut inner = self.inner.lock().await;
+        inner.refill();
+
+        let now = Instant::now();
+        BucketSnapshot {
+            capacity: inner.capacity,
+            refill_rate: inner.refill_rate,
+            tokens: inner.tokens,
+            backoff_remaining: inner
+                .pause_until
+                .filter(|until| *until > now)
+                .map(|until| until - now),
+        }
+    }
+
+    /// Saves the bucket's configuration, tokens and active backoff period to `path`, to be restored
+    /// with [`TokenBucket::load_state`] after a restart.
+    ///
+    /// Times are stored as wall-clock timestamps. The history behind
+    /// [`TokenBucket::consumed_last_minute`] and the Retry-After cap are not saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write (replaced atomically)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// bucket.save_state(Path::new("bucket.json")).await?;
+    /// ```
+    pub async fn save_state(&self, path: &Path) -> std::io::Result<()> {
+        let state = {
+            let mut inner = self.inner.lock().await;
+            inner.refill();
+
+            let now = Instant::now();
+            let wall_now = SystemTime::now();
+            PersistedState {
+                capacity: inner.capacity,
+                refill_rate: inner.refill_rate,
+                tokens: inner.tokens as f64 + inner.remainder,
+                saved_at_ms: unix_millis(wall_now),
+                backoff_until_ms: inner
+                    .pause_until
+                    .filter(|until| *until > now)
+                    .map(|until| unix_millis(wall_now + (until - now))),
+            }
+        };
+
+        let json = serde_json::to_vec(&state).map_err(std::io::Error::other)?;
+        crate::shared::write_atomic_async(path, json).await
+    }
+
+    /// Restores a bucket saved with [`TokenBucket::save_state`].
+    ///
+    /// Tokens are refilled for the wall-clock time elapsed since the state was saved, so a
+    /// restart neither hands out a full bucket nor loses the refill it missed. A backoff period
+    /// that hasn't ended yet is resumed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file written by [`TokenBucket::save_state`]
+    ///
+    /// # Returns
+    ///
+    /// * `Some(TokenBucket)` - The restored bucket
+    /// * `None` - If the file is missing or corrupt (logged), including a zero capacity or a refill
+    ///   rate that isn't a positive number, to start with a fresh bucket instead
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let bucket = TokenBucket::load_state(Path::new("bucket.json"))
+    ///     .unwrap_or_else(|| TokenBucket::new(10, 2.0));
+    /// ```
+    pub fn load_state(path: &Path) -> Option<Self> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                eprintln!("Failed to read token bucket state {}: {e}", path.display());
+                return None;
+            }
+        };
+        let state: PersistedState = match serde_json::from_slice(&bytes) {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!(
+                    "Ignoring corrupt token bucket state {}: {e}",
+                    path.display()
+                );
+                return None;
+            }
+        };
+
+        if state.capacity == 0 || !(state.refill_rate.is_finite() && state.refill_rate > 0.0) {
+            eprintln!(
+                "Ignoring corrupt token bucket state {}: capacity {} and refill rate {} can't \
+                 limit requests",
+                path.display(),
+                state.capacity,
+                state.refill_rate
+            );
+            return None;
+        }
+
+        let now_ms = unix_millis(SystemTime::now());
+        // A clock set back since saving counts as no time elapsed
+        let elapsed = now_ms.saturating_sub(state.saved_at_ms) as f64 / 1000.0;
+        let tokens =
+            (state.tokens.max(0.0) + elapsed * state.refill_rate).min(state.capacity as f64);
+
+        let bucket = Self::new(state.capacity, state.refill_rate);
+        {
+            let mut inner = bucket
+                .inner
+                .try_lock()
+                .expect("a new bucket isn't shared yet");
+            inner.tokens = tokens as u32;
+            inner.remainder = tokens - tokens.trunc();
+            inner.pause_until = state
+                .backoff_until_ms
+                .filter(|until| *until > now_ms)
+                .map(|until| Instant::now() + Duration::from_millis(until - now_ms));
+        }
+        Some(bucket)
+    }
+
+    /// Returns how long until [`TokenBucket::consume`] can take a token, e.g. for a countdown
+    /// while throttled.
+    ///
+    /// Accounts for the tokens left, the refill rate (including the fractional refill already
+    /// accumulated) and any backoff period, whichever ends last.
+    ///
+    /// # Returns
+    ///
+    /// * `None` - A token is available right now
+    /// * `Some(Duration)` - The time left until one is (`Duration::MAX` if the bucket never
+    ///   refills)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// if let Some(wait) = bucket.time_until_next_token().await {
+    ///     println!("Next request in {:.1}s", wait.as_secs_f64());
+    /// }
+    /// ```
+    pub async fn time_until_next_token(&self) -> Option<Duration> {
+        let mut inner = self.inner.lock().await;
+        inner.refill();
+        inner.time_until_next_token(Instant::now())
+    }
+
+    /// Returns whether the bucket is throttling, with the tokens available, the time until the
+    /// next token and whether a backoff period is active, all taken at the same moment.
+    ///
+    /// Tokens are refilled first, so the figures are current.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let health = bucket.health().await;
+    /// if health.throttling {
+    ///     println!("Throttled: {health:?}");
+    /// }
+    /// ```
+    pub async fn health(&self) -> BucketHealth {
+        let mut inner = self.inner.lock().await;
+        inner.refill();
+
+        let now = Instant::now();
+        let time_to_next_token = inner.time_until_next_token(now);
+        BucketHealth {
+            throttling: time_to_next_token.is_some(),
+            tokens: inner.tokens,
+            time_to_next_token,
+            in_backoff: inner.pause_until.is_some_and(|until| until > now),
+        }
+    }
+
+    /// Returns the number of tokens consumed within the last minute.
+    ///
+    /// Per-minute API limits (such as HIBP's requests per minute) count requests over a sliding
+    /// minute, which the bucket's token count alone doesn't tell.
+    ///
+    /// # Returns
+    ///
+    /// The number of tokens taken by [`TokenBucket::consume`] in the last 60 seconds
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let used = bucket.consumed_last_minute().await;
+    /// println!("Requests made in the last minute: {}", used);
+    /// ```
+    pub async fn consumed_last_minute(&self) -> u32 {
+        let mut inner = self.inner.lock().await;
+        inner.prune_consumptions();
+        inner.consumed_at.len() as u32
+    }
+
     // / Returns the number of tokens currently available in the bucket.
     // /
     // / This method provides a snapshot of the current token count. The actual number
@@ -350,6 +651,13 @@ impl TokenBucket {
     // }
 }
 
+/// Converts a wall-clock time to milliseconds since the Unix epoch (0 for earlier times).
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 impl TokenBucketInner {
     /// Refills tokens based on the elapsed time since the last refill.
     ///
@@ -374,4 +682,191 @@ impl TokenBucketInner {
             self.last_refill = now;
         }
     }
+
+    /// Returns how long after `now` a token can be taken, `None` if one can be taken right away.
+    /// See [`TokenBucket::time_until_next_token`]; expects [`TokenBucketInner::refill`] to have
+    /// just run.
+    fn time_until_next_token(&self, now: Instant) -> Option<Duration> {
+        let backoff = self
+            .pause_until
+            .filter(|until| *until > now)
+            .map(|until| until - now);
+
+        let refill = if self.tokens > 0 {
+            None
+        } else if self.refill_rate > 0.0 {
+            // `refill` just ran, so only the missing fraction of a token is left to accumulate
+            let missing = (1.0 - self.remainder).max(0.0);
+            Some(Duration::from_secs_f64(missing / self.refill_rate))
+        } else {
+            Some(Duration::MAX)
+        };
+
+        match (refill, backoff) {
+            (Some(refill), Some(backoff)) => Some(refill.max(backoff)),
+            (refill, backoff) => refill.or(backoff),
+        }
+    }
+
+    /// Records a consumed token in the sliding window used by
+    /// [`TokenBucket::consumed_last_minute`].
+    fn record_consumption(&mut self) {
+        self.prune_consumptions();
+        self.consumed_at.push_back(Instant::now());
+    }
+
+    /// Drops consumption timestamps older than the sliding window.
+    fn prune_consumptions(&mut self) {
+        let now = Instant::now();
+        while self
+            .consumed_at
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= CONSUMPTION_WINDOW)
+        {
+            self.consumed_at.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a path in the temporary directory unique to this test run.
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("emicon-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn load_state_rejects_buckets_that_cant_refill() {
+        let path = temp_path("bucket-invalid.json");
+        for (capacity, refill_rate) in [("10", "0.0"), ("10", "-1.0"), ("10", "null"), ("0", "2.0")]
+        {
+            let json = format!(
+                r#"{{"capacity":{capacity},"refill_rate":{refill_rate},"tokens":5.0,"saved_at_ms":0,"backoff_until_ms":null}}"#
+            );
+            std::fs::write(&path, json).unwrap();
+            assert!(
+                TokenBucket::load_state(&path).is_none(),
+                "capacity {capacity}, refill rate {refill_rate}"
+            );
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_consume_does_not_starve_other_consumers() {
+        let bucket = TokenBucket::new(1, 1.0);
+        bucket.consume().await;
+
+        // Gives up while waiting for the next token
+        let cancelled = tokio::time::timeout(Duration::from_millis(500), bucket.consume()).await;
+        assert!(cancelled.is_err());
+
+        let consumers: Vec<_> = (0..2)
+            .map(|_| {
+                let bucket = bucket.clone();
+                tokio::spawn(async move { bucket.consume().await })
+            })
+            .collect();
+        for consumer in consumers {
+            tokio::time::timeout(Duration::from_secs(5), consumer)
+                .await
+                .expect("a waiting consumer was starved")
+                .unwrap();
+        }
+
+        // The cancelled call neither took nor reserved a token
+        assert_eq!(bucket.consumed_last_minute().await, 3);
+        assert_eq!(bucket.snapshot().await.tokens, 0);
+    }
+
+    #[test]
+    fn parse_retry_after_uses_the_bucket_cap() {
+        let bucket = TokenBucket::new(10, 2.0).with_max_retry_after(Duration::from_secs(600));
+        assert_eq!(bucket.parse_retry_after("300"), Duration::from_secs(300));
+        assert_eq!(bucket.parse_retry_after("86400"), Duration::from_secs(600));
+        assert_eq!(
+            TokenBucket::new(10, 2.0).parse_retry_after("86400"),
+            DEFAULT_MAX_RETRY_AFTER
+        );
+    }
+
+    #[test]
+    fn parse_retry_after_falls_back_on_malformed_values() {
+        let bucket = TokenBucket::new(10, 2.0);
+        assert_eq!(bucket.parse_retry_after("soon"), Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn load_state_restores_a_saved_bucket() {
+        let path = temp_path("bucket-valid.json");
+        TokenBucket::new(10, 2.0).save_state(&path).await.unwrap();
+        let bucket = TokenBucket::load_state(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let snapshot = bucket.snapshot().await;
+        assert_eq!(snapshot.capacity, 10);
+        assert_eq!(snapshot.refill_rate, 2.0);
+        assert_eq!(snapshot.tokens, 10);
+    }
+
+    /// Writes a saved state to `path` as [`TokenBucket::save_state`] would have, `saved_ago`
+    /// before now.
+    fn write_state(path: &Path, tokens: f64, saved_ago: Duration, backoff_until_ms: Option<u64>) {
+        let state = PersistedState {
+            capacity: 10,
+            refill_rate: 1.0,
+            tokens,
+            saved_at_ms: unix_millis(SystemTime::now() - saved_ago),
+            backoff_until_ms,
+        };
+        std::fs::write(path, serde_json::to_vec(&state).unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn load_state_refills_for_the_time_since_saving() {
+        let path = temp_path("bucket-drained.json");
+        write_state(&path, 0.0, Duration::from_millis(3_200), None);
+        let bucket = TokenBucket::load_state(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bucket.snapshot().await.tokens, 3);
+    }
+
+    #[tokio::test]
+    async fn load_state_caps_the_refill_at_capacity() {
+        let path = temp_path("bucket-old.json");
+        write_state(&path, 0.0, Duration::from_secs(60 * 60), None);
+        let bucket = TokenBucket::load_state(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bucket.snapshot().await.tokens, 10);
+    }
+
+    #[tokio::test]
+    async fn load_state_resumes_an_unfinished_backoff() {
+        let path = temp_path("bucket-backoff.json");
+        let until = unix_millis(SystemTime::now() + Duration::from_secs(60));
+        write_state(&path, 5.0, Duration::ZERO, Some(until));
+        let bucket = TokenBucket::load_state(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let remaining = bucket.snapshot().await.backoff_remaining.unwrap();
+        assert!(
+            remaining > Duration::from_secs(50) && remaining <= Duration::from_secs(60),
+            "{remaining:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn load_state_drops_a_finished_backoff() {
+        let path = temp_path("bucket-backoff-over.json");
+        let until = unix_millis(SystemTime::now() - Duration::from_secs(1));
+        write_state(&path, 5.0, Duration::from_secs(10), Some(until));
+        let bucket = TokenBucket::load_state(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(bucket.snapshot().await.backoff_remaining.is_none());
+    }
 }
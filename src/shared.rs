@@ -0,0 +1,533 @@
+//! Items shared between the application modules.
+
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+/// Errors that can arise anywhere in the application.
+#[derive(Debug, thiserror::Error)]
+pub enum EmiconError {
+    #[error(transparent)]
+    Hibp(#[from] crate::hibp::HibpError),
+    #[error("Slint Error: {0}")]
+    Slint(#[from] slint::PlatformError),
+    #[error("JSON parsing failed: {0}")]
+    JsonParse(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Clipboard error: {0}")]
+    Clipboard(#[from] arboard::Error),
+}
+
+/// Wrapper type used for all results returned by the application.
+pub type EmiconResult<T> = std::result::Result<T, EmiconError>;
+
+/// Masks an email address for display, keeping only the first character of the local part and of
+/// the domain name, plus the top-level domain (e.g. `john@example.com` -> `j***@e***.com`).
+///
+/// Only meant for presentation: queries must still use the real address.
+/// Input without an `@` is masked the same way as a local part.
+pub fn redact_email(email: &str) -> String {
+    fn mask(part: &str) -> String {
+        match part.chars().next() {
+            Some(first) => format!("{first}***"),
+            None => String::new(),
+        }
+    }
+
+    let email = email.trim();
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return mask(email);
+    };
+
+    match domain.rsplit_once('.') {
+        Some((name, tld)) => format!("{}@{}.{tld}", mask(local), mask(name)),
+        None => format!("{}@{}", mask(local), mask(domain)),
+    }
+}
+
+/// Returns a stable, non-reversible identifier for an email address: the lowercase hex SHA-256 of
+/// the trimmed, lowercased address. Used wherever an email needs a key (e.g. cache file names)
+/// without storing the address itself.
+pub fn email_fingerprint(email: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    hex::encode(Sha256::digest(email.trim().to_lowercase().as_bytes()))
+}
+
+/// Disposable email domains bundled with the application (see [`bundled_disposable_domains`]).
+const BUNDLED_DISPOSABLE_DOMAINS: &str = include_str!("disposable_domains.txt");
+
+/// Returns true if the email's domain, or a domain it's a subdomain of, is in `domains`
+/// (lowercase, as [`parse_domain_list`] returns them). Meant to warn about throwaway addresses,
+/// not to refuse them. Input without an `@` returns false.
+pub fn is_disposable_email(email: &str, domains: &HashSet<String>) -> bool {
+    let Some((_, domain)) = email.trim().rsplit_once('@') else {
+        return false;
+    };
+
+    let mut domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    loop {
+        if domains.contains(&domain) {
+            return true;
+        }
+        match domain.split_once('.') {
+            Some((_, parent)) if parent.contains('.') => domain = parent.to_string(),
+            _ => return false,
+        }
+    }
+}
+
+/// Parses a domain list: one domain per line, blank lines and `#` comments ignored. Domains are
+/// lowercased.
+pub fn parse_domain_list(list: &str) -> HashSet<String> {
+    list.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
+
+/// Reads a domain list file in the format [`parse_domain_list`] accepts.
+pub fn load_domain_list(path: &Path) -> io::Result<HashSet<String>> {
+    Ok(parse_domain_list(&fs::read_to_string(path)?))
+}
+
+/// Returns the disposable email domains bundled with the application.
+pub fn bundled_disposable_domains() -> HashSet<String> {
+    parse_domain_list(BUNDLED_DISPOSABLE_DOMAINS)
+}
+
+/// Converts an HTML snippet (such as a breach description) to plain text by dropping its tags.
+/// Entities (e.g. `&amp;`) are left as they are.
+pub fn html_to_text(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut inside_tag = false;
+
+    for ch in input.chars() {
+        match ch {
+            '<' => inside_tag = true,
+            '>' => inside_tag = false,
+            _ => {
+                if !inside_tag {
+                    result.push(ch);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Formats a count with thousands separators (e.g. `1240` -> `"1,240"`).
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            result.push(',');
+        }
+        result.push(ch);
+    }
+
+    result
+}
+
+/// How dates are displayed (see [`format_breach_date`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateStyle {
+    /// ISO 8601, e.g. "2015-10-21". Always used in exports.
+    #[default]
+    Iso,
+    /// Day, month name and year, e.g. "21 October 2015".
+    Long,
+    /// Time elapsed since the date, e.g. "3 years ago".
+    Relative,
+}
+
+/// Order of the breaches found for an email.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BreachOrder {
+    /// Most severe first (see [`crate::hibp::breach_severity`]), then newest first.
+    #[default]
+    Severity,
+    /// Breaches exposing credentials first (see [`crate::hibp::exposes_credentials`]), otherwise
+    /// in HIBP's order.
+    Credentials,
+}
+
+/// Formats a breach date for display in the given style. Relative dates are measured from
+/// `today` (e.g. the local date), and dates in the future are shown as "today".
+pub fn format_breach_date(date: NaiveDate, style: DateStyle, today: NaiveDate) -> String {
+    match style {
+        DateStyle::Iso => date.to_string(),
+        DateStyle::Long => date.format("%-d %B %Y").to_string(),
+        DateStyle::Relative => {
+            let days = (today - date).num_days();
+            let (amount, unit) = match days {
+                ..=0 => return "today".to_string(),
+                1..=29 => (days, "day"),
+                30..=364 => (days / 30, "month"),
+                _ => (days / 365, "year"),
+            };
+            let plural = if amount == 1 { "" } else { "s" };
+            format!("{amount} {unit}{plural} ago")
+        }
+    }
+}
+
+/// A daily period in local time during which alerts are held back (e.g. 22:00 to 07:00). It may
+/// wrap past midnight; a period whose start equals its end is empty. Times are written as
+/// `"HH:MM:SS"` in the settings file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    /// End of the period, itself not included.
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Returns true if `time` falls within the period.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// Returns true if the current local time falls within the period.
+    pub fn is_active(&self) -> bool {
+        self.contains(chrono::Local::now().time())
+    }
+}
+
+/// Default cap on the wait [`parse_retry_after`] returns.
+pub const DEFAULT_MAX_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Parses a `Retry-After` header value, either a number of seconds or an HTTP date, into how long
+/// to wait. A date in the past means no wait. Returns `None` if the value is neither.
+///
+/// HTTP dates are compared against the local clock, so a clock running behind makes the wait look
+/// longer than intended. Waits above `max` are most likely that or a malformed header, so they're
+/// logged and capped at `max` rather than stalling for days.
+pub fn parse_retry_after(value: &str, max: std::time::Duration) -> Option<std::time::Duration> {
+    let trimmed = value.trim();
+    let wait = match trimmed.parse::<u64>() {
+        Ok(secs) => std::time::Duration::from_secs(secs),
+        Err(_) => httpdate::parse_http_date(trimmed)
+            .ok()?
+            .duration_since(std::time::SystemTime::now())
+            .unwrap_or_default(),
+    };
+
+    if wait > max {
+        eprintln!(
+            "Retry-After \"{value}\" implies an implausible {}s wait (clock skew or malformed \
+             header?), waiting {}s instead",
+            wait.as_secs(),
+            max.as_secs()
+        );
+        return Some(max);
+    }
+    Some(wait)
+}
+
+/// Writes `bytes` to `path` atomically: they go to a temporary file next to it first, which then
+/// replaces `path`. A crash or failed write leaves the previous file intact instead of a truncated
+/// one. All files the application persists are written through this.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = fs::File::create(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// [`write_atomic`] for async code: the blocking file operations run on Tokio's blocking thread
+/// pool instead of stalling the runtime.
+pub async fn write_atomic_async(path: &Path, bytes: Vec<u8>) -> io::Result<()> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || write_atomic(&path, &bytes))
+        .await
+        .map_err(io::Error::other)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Returns an empty directory unique to the test `name`.
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("emicon-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Formats the time `offset` from now as an HTTP date, in the past if `offset` is negative.
+    fn http_date_in(offset: i64) -> String {
+        let now = std::time::SystemTime::now();
+        let delta = std::time::Duration::from_secs(offset.unsigned_abs());
+        httpdate::fmt_http_date(if offset < 0 { now - delta } else { now + delta })
+    }
+
+    #[test]
+    fn parse_retry_after_reads_seconds() {
+        let max = DEFAULT_MAX_RETRY_AFTER;
+        assert_eq!(
+            parse_retry_after("120", max),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_retry_after(" 0 ", max), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn parse_retry_after_caps_long_waits() {
+        let max = Duration::from_secs(600);
+        assert_eq!(parse_retry_after("600", max), Some(max));
+        assert_eq!(parse_retry_after("601", max), Some(max));
+        assert_eq!(parse_retry_after("999999999", max), Some(max));
+    }
+
+    #[test]
+    fn parse_retry_after_reads_http_dates() {
+        let wait = parse_retry_after(&http_date_in(120), DEFAULT_MAX_RETRY_AFTER).unwrap();
+        // HTTP dates have whole-second precision
+        assert!(wait > Duration::from_secs(115) && wait <= Duration::from_secs(120));
+    }
+
+    #[test]
+    fn parse_retry_after_treats_past_dates_as_no_wait() {
+        let max = DEFAULT_MAX_RETRY_AFTER;
+        assert_eq!(
+            parse_retry_after(&http_date_in(-120), max),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", max),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn parse_retry_after_caps_skewed_dates() {
+        // A date days ahead, as a local clock running behind would make it look
+        let max = DEFAULT_MAX_RETRY_AFTER;
+        assert_eq!(
+            parse_retry_after(&http_date_in(3 * 24 * 3600), max),
+            Some(max)
+        );
+        let max = Duration::from_secs(60);
+        assert_eq!(parse_retry_after(&http_date_in(3600), max), Some(max));
+    }
+
+    #[test]
+    fn parse_retry_after_rejects_other_values() {
+        let max = DEFAULT_MAX_RETRY_AFTER;
+        for value in ["", "soon", "-5", "1.5", "21 Oct 2015"] {
+            assert_eq!(parse_retry_after(value, max), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file() {
+        let dir = temp_dir("write-atomic-replace");
+        let path = dir.join("config.json");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(!dir.join(".config.json.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_write_atomic_leaves_the_file_intact() {
+        let dir = temp_dir("write-atomic-failure");
+        let path = dir.join("config.json");
+        fs::write(&path, "old").unwrap();
+        // A directory where the temporary file goes makes creating it fail
+        fs::create_dir(dir.join(".config.json.tmp")).unwrap();
+
+        assert!(write_atomic(&path, b"new").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn time(hour: u32, min: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+    }
+
+    #[test]
+    fn quiet_hours_wrap_past_midnight() {
+        let night = QuietHours {
+            start: time(22, 0),
+            end: time(7, 0),
+        };
+        assert!(night.contains(time(22, 0)));
+        assert!(night.contains(time(23, 0)));
+        assert!(night.contains(time(0, 0)));
+        assert!(night.contains(time(3, 0)));
+        assert!(!night.contains(time(7, 0)));
+        assert!(!night.contains(time(12, 0)));
+        assert!(!night.contains(time(21, 59)));
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        let lunch = QuietHours {
+            start: time(12, 0),
+            end: time(13, 30),
+        };
+        assert!(lunch.contains(time(12, 0)));
+        assert!(lunch.contains(time(13, 29)));
+        assert!(!lunch.contains(time(13, 30)));
+        assert!(!lunch.contains(time(11, 59)));
+        assert!(!lunch.contains(time(23, 0)));
+    }
+
+    #[test]
+    fn quiet_hours_with_equal_start_and_end_are_empty() {
+        let empty = QuietHours {
+            start: time(8, 0),
+            end: time(8, 0),
+        };
+        for hour in 0..24 {
+            assert!(!empty.contains(time(hour, 0)), "{hour}:00");
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn format_breach_date_iso_and_long() {
+        let today = date(2024, 1, 1);
+        let breached = date(2015, 10, 21);
+        assert_eq!(
+            format_breach_date(breached, DateStyle::Iso, today),
+            "2015-10-21"
+        );
+        assert_eq!(
+            format_breach_date(breached, DateStyle::Long, today),
+            "21 October 2015"
+        );
+        assert_eq!(
+            format_breach_date(date(2015, 3, 5), DateStyle::Long, today),
+            "5 March 2015"
+        );
+    }
+
+    #[test]
+    fn format_breach_date_relative_boundaries() {
+        let today = date(2024, 6, 30);
+        let relative = |days: i64| {
+            let breached = today - chrono::Duration::days(days);
+            format_breach_date(breached, DateStyle::Relative, today)
+        };
+        assert_eq!(relative(0), "today");
+        assert_eq!(relative(1), "1 day ago");
+        assert_eq!(relative(2), "2 days ago");
+        assert_eq!(relative(29), "29 days ago");
+        assert_eq!(relative(30), "1 month ago");
+        assert_eq!(relative(59), "1 month ago");
+        assert_eq!(relative(60), "2 months ago");
+        assert_eq!(relative(364), "12 months ago");
+        assert_eq!(relative(365), "1 year ago");
+        assert_eq!(relative(729), "1 year ago");
+        assert_eq!(relative(730), "2 years ago");
+    }
+
+    #[test]
+    fn format_breach_date_relative_future_is_today() {
+        let today = date(2024, 6, 30);
+        assert_eq!(
+            format_breach_date(date(2024, 7, 1), DateStyle::Relative, today),
+            "today"
+        );
+        assert_eq!(
+            format_breach_date(date(2030, 1, 1), DateStyle::Relative, today),
+            "today"
+        );
+    }
+
+    #[test]
+    fn parse_domain_list_skips_comments_and_blank_lines() {
+        let list = "# Disposable domains
+            Mailinator.com
+
+              guerrillamail.com  # popular\r
+            #tempmail.com
+            10minutemail.com";
+        let expected: HashSet<String> = ["mailinator.com", "guerrillamail.com", "10minutemail.com"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        assert_eq!(parse_domain_list(list), expected);
+        assert!(parse_domain_list("").is_empty());
+    }
+
+    #[test]
+    fn is_disposable_email_matches_domains_and_subdomains_in_any_case() {
+        let domains = parse_domain_list("mailinator.com\nguerrillamail.com");
+        assert!(is_disposable_email("test@mailinator.com", &domains));
+        assert!(is_disposable_email(" Test@MAILINATOR.com ", &domains));
+        assert!(is_disposable_email("test@mailinator.com.", &domains));
+        assert!(is_disposable_email("test@eu.mx.mailinator.com", &domains));
+        assert!(!is_disposable_email("test@notmailinator.com", &domains));
+        assert!(!is_disposable_email(
+            "test@mailinator.com.example.org",
+            &domains
+        ));
+        assert!(!is_disposable_email("test@example.com", &domains));
+    }
+
+    #[test]
+    fn is_disposable_email_needs_an_at_sign() {
+        let domains = parse_domain_list("mailinator.com");
+        assert!(!is_disposable_email("mailinator.com", &domains));
+        assert!(!is_disposable_email("", &domains));
+    }
+
+    #[test]
+    fn redact_email_keeps_first_characters_and_tld() {
+        assert_eq!(redact_email("john@example.com"), "j***@e***.com");
+        assert_eq!(redact_email("  john@example.com "), "j***@e***.com");
+        assert_eq!(redact_email("j@e.io"), "j***@e***.io");
+        assert_eq!(redact_email("john@mail.example.co.uk"), "j***@m***.uk");
+        assert_eq!(redact_email("a@b@example.com"), "a***@e***.com");
+    }
+
+    #[test]
+    fn redact_email_masks_unusual_input() {
+        assert_eq!(redact_email("john"), "j***");
+        assert_eq!(redact_email("john@localhost"), "j***@l***");
+        assert_eq!(redact_email("@example.com"), "@e***.com");
+        assert_eq!(redact_email(""), "");
+    }
+}